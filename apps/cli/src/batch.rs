@@ -0,0 +1,382 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::Context;
+use console::style;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use crate::{
+    JobArgs,
+    pipeline::PipelineHandle,
+    workers::{cli_completion_sink::PipelineOutcome, events::JobSpec},
+};
+
+/// How one input of a batch run ended
+#[derive(Debug)]
+pub enum RunStatus {
+    Report {
+        title: String,
+        path: PathBuf,
+        cost_usd: f64,
+    },
+    /// A playlist or channel, expanded into its own indexed batch
+    Playlist {
+        index_path: PathBuf,
+        completed: usize,
+        total: usize,
+    },
+    Failed {
+        stage: String,
+        message: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct RunEntry {
+    pub input: String,
+    pub status: RunStatus,
+    /// Stages, and LLM calls by stage, that were served from the cache
+    pub cache_hits: Vec<String>,
+}
+
+impl RunEntry {
+    fn failed(input: String, stage: &str, message: String) -> Self {
+        Self {
+            input,
+            status: RunStatus::Failed {
+                stage: stage.to_string(),
+                message,
+            },
+            cache_hits: Vec::new(),
+        }
+    }
+
+    fn from_outcome(input: String, outcome: PipelineOutcome) -> Self {
+        match outcome {
+            PipelineOutcome::Job(Ok(done)) => {
+                let mut cache_hits = done.cache_hits;
+                cache_hits.extend(
+                    done.usage
+                        .calls
+                        .iter()
+                        .filter(|call| call.cached)
+                        .map(|call| call.stage.clone()),
+                );
+                Self {
+                    input,
+                    status: RunStatus::Report {
+                        title: done.report.title,
+                        path: done.report_path,
+                        cost_usd: done.usage.total_cost_usd(),
+                    },
+                    cache_hits,
+                }
+            }
+            PipelineOutcome::Job(Err(failed)) => Self::failed(input, failed.stage, failed.message),
+            PipelineOutcome::Planned { .. } => unreachable!("run_jobs skips batch plans"),
+            PipelineOutcome::Batch(done) => Self {
+                input,
+                status: RunStatus::Playlist {
+                    completed: done.index.completed(),
+                    total: done.index.entries.len(),
+                    index_path: done.index_path,
+                },
+                cache_hits: Vec::new(),
+            },
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, RunStatus::Failed { .. })
+    }
+}
+
+/// Read the inputs of a batch run from `path`, or from stdin for `-`
+pub async fn read_inputs(path: &str) -> anyhow::Result<Vec<String>> {
+    let text = if path == "-" {
+        let mut text = String::new();
+        tokio::io::stdin().read_to_string(&mut text).await?;
+        text
+    } else {
+        tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read {path}"))?
+    };
+    Ok(parse_inputs(&text))
+}
+
+/// One input per line; blank lines and `#` comments are skipped
+pub fn parse_inputs(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Turn every input into a job and run them all on `pipeline`
+pub async fn run_inputs(
+    pipeline: &mut PipelineHandle,
+    inputs: Vec<String>,
+    args: &JobArgs,
+) -> Vec<RunEntry> {
+    let mut jobs = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let job = JobSpec::from_args(&input, args);
+        jobs.push((input, job));
+    }
+    run_jobs(pipeline, jobs).await
+}
+
+/// Run the jobs and collect one entry per job, in input order
+///
+/// Jobs go in as earlier ones finish, at most
+/// [`StageConcurrency::jobs_in_flight`](crate::pipeline::StageConcurrency::jobs_in_flight)
+/// at a time, so long input lists never overflow the stage queues. Inputs that
+/// could not be turned into a job are reported as failed at the `input` stage
+/// without touching the pipeline.
+pub async fn run_jobs(
+    pipeline: &mut PipelineHandle,
+    jobs: Vec<(String, anyhow::Result<JobSpec>)>,
+) -> Vec<RunEntry> {
+    let total = jobs.len();
+    let max_in_flight = pipeline.concurrency.jobs_in_flight();
+    let mut entries: Vec<Option<RunEntry>> = (0..total).map(|_| None).collect();
+    let mut pending: HashMap<Uuid, (usize, String)> = HashMap::new();
+    let mut queued = jobs.into_iter().enumerate();
+    let mut finished = 0;
+
+    loop {
+        while pending.len() < max_in_flight
+            && let Some((index, (input, job))) = queued.next()
+        {
+            match job {
+                Ok(job) => {
+                    pending.insert(job.job_id, (index, input));
+                    pipeline.bus.publish(job.into_request());
+                }
+                Err(e) => {
+                    let entry = RunEntry::failed(input, "input", format!("{e:#}"));
+                    finished += 1;
+                    report_progress(&entry, finished, total);
+                    entries[index] = Some(entry);
+                }
+            }
+        }
+        if pending.is_empty() {
+            break;
+        }
+
+        let Some(outcome) = pipeline.next_outcome().await else {
+            break;
+        };
+
+        let job_id = match &outcome {
+            PipelineOutcome::Job(Ok(done)) => done.job_id,
+            PipelineOutcome::Job(Err(failed)) => match failed.correlation_id {
+                Some(job_id) => job_id,
+                None => continue,
+            },
+            PipelineOutcome::Planned { .. } => continue,
+            PipelineOutcome::Batch(done) => done.job_id,
+        };
+        // outcomes of videos inside a playlist belong to its own index
+        let Some((index, input)) = pending.remove(&job_id) else {
+            continue;
+        };
+
+        let entry = RunEntry::from_outcome(input, outcome);
+        finished += 1;
+        report_progress(&entry, finished, total);
+        entries[index] = Some(entry);
+    }
+
+    let stopped = pending
+        .into_values()
+        .chain(queued.filter_map(|(index, (input, job))| job.is_ok().then_some((index, input))));
+    for (index, input) in stopped {
+        entries[index] = Some(RunEntry::failed(
+            input,
+            "pipeline",
+            "pipeline stopped before the job finished".to_string(),
+        ));
+    }
+
+    entries.into_iter().flatten().collect()
+}
+
+fn report_progress(entry: &RunEntry, finished: usize, total: usize) {
+    match &entry.status {
+        RunStatus::Report { title, path, .. } => println!(
+            "{} [{finished}/{total}] {title} ({})",
+            style("✓").green().bold(),
+            path.display()
+        ),
+        RunStatus::Playlist {
+            index_path,
+            completed,
+            total: videos,
+        } => println!(
+            "{} [{finished}/{total}] {} ({completed}/{videos} reports, index at {})",
+            style("✓").green().bold(),
+            entry.input,
+            index_path.display()
+        ),
+        RunStatus::Failed { stage, message } => eprintln!(
+            "{} [{finished}/{total}] {} failed at {stage}: {message}",
+            style("✗").red().bold(),
+            entry.input
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bratishka_core::events::BusConfig;
+
+    use super::*;
+    use crate::{
+        pipeline::{StageConcurrency, start_pipeline},
+        provider::Provider,
+        testing::{
+            FakeMedia, MockLlmServer, MockReply, REPORT_FIXTURE, REPORT_REQUEST_NEEDLE,
+            SECTIONS_FIXTURE, SECTIONS_REQUEST_NEEDLE, transcribed_job,
+        },
+        workers::STAGE_QUEUE_CAPACITY,
+    };
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        let inputs = parse_inputs(
+            "https://youtu.be/a\n\n  # talks from last week\n  ./talk.mp3  \nhttps://youtu.be/b\n",
+        );
+        assert_eq!(
+            inputs,
+            vec!["https://youtu.be/a", "./talk.mp3", "https://youtu.be/b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn runs_jobs_concurrently_and_reports_each() {
+        let server = MockLlmServer::start().await;
+        server
+            .script(
+                SECTIONS_REQUEST_NEEDLE,
+                vec![MockReply::content(SECTIONS_FIXTURE)],
+            )
+            .await;
+        server
+            .script(
+                REPORT_REQUEST_NEEDLE,
+                vec![MockReply::content(REPORT_FIXTURE)],
+            )
+            .await;
+        let media = FakeMedia::default();
+        let roots: Vec<_> = (0..3).map(|_| tempfile::tempdir().unwrap()).collect();
+
+        let mut jobs = Vec::new();
+        for (i, root) in roots.iter().enumerate() {
+            let mut job = transcribed_job(Provider::Grok, root.path(), &server.base_url());
+            if i == 1 {
+                // fails before its first LLM call
+                job.max_cost_usd = Some(0.0);
+            }
+            jobs.push((format!("job-{i}"), Ok(job)));
+        }
+        jobs.push((
+            "not a url".to_string(),
+            Err(anyhow::anyhow!("unsupported input")),
+        ));
+
+        let mut pipeline = start_pipeline(
+            BusConfig {
+                session_id: Uuid::new_v4(),
+                strict_routing: false,
+            },
+            media.tools(),
+            StageConcurrency {
+                media: 3,
+                transcribe: 1,
+                llm: 3,
+            },
+        )
+        .await
+        .unwrap();
+        let entries = tokio::time::timeout(Duration::from_secs(10), run_jobs(&mut pipeline, jobs))
+            .await
+            .expect("batch run timed out");
+        let _ = pipeline.shutdown_tx.send(());
+
+        let inputs: Vec<_> = entries.iter().map(|e| e.input.as_str()).collect();
+        assert_eq!(inputs, vec!["job-0", "job-1", "job-2", "not a url"]);
+
+        assert!(matches!(entries[0].status, RunStatus::Report { .. }));
+        assert!(matches!(entries[2].status, RunStatus::Report { .. }));
+        assert!(
+            entries[0]
+                .cache_hits
+                .contains(&"transcribe.audio".to_string())
+        );
+        match &entries[1].status {
+            RunStatus::Failed { stage, message } => {
+                assert_eq!(stage, "analyze.sections");
+                assert!(message.contains("Budget exceeded"), "{message}");
+            }
+            other => panic!("expected a failure, got {other:?}"),
+        }
+        match &entries[3].status {
+            RunStatus::Failed { stage, .. } => assert_eq!(stage, "input"),
+            other => panic!("expected a failure, got {other:?}"),
+        }
+        assert_eq!(media.fetcher.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn runs_more_jobs_than_a_stage_queue_holds() {
+        let server = MockLlmServer::start().await;
+        let media = FakeMedia::default();
+        let total = STAGE_QUEUE_CAPACITY + 1;
+        let roots: Vec<_> = (0..total).map(|_| tempfile::tempdir().unwrap()).collect();
+
+        let jobs = roots
+            .iter()
+            .enumerate()
+            .map(|(i, root)| {
+                let mut job = transcribed_job(Provider::Grok, root.path(), &server.base_url());
+                // fails before its first LLM call, so the run stays quick
+                job.max_cost_usd = Some(0.0);
+                (format!("job-{i}"), Ok(job))
+            })
+            .collect();
+
+        let mut pipeline = start_pipeline(
+            BusConfig {
+                session_id: Uuid::new_v4(),
+                strict_routing: false,
+            },
+            media.tools(),
+            StageConcurrency {
+                media: 2,
+                transcribe: 1,
+                llm: 2,
+            },
+        )
+        .await
+        .unwrap();
+        let entries = tokio::time::timeout(Duration::from_secs(120), run_jobs(&mut pipeline, jobs))
+            .await
+            .expect("batch run timed out");
+        let _ = pipeline.shutdown_tx.send(());
+
+        assert_eq!(entries.len(), total);
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.input, format!("job-{i}"));
+            match &entry.status {
+                RunStatus::Failed { stage, .. } => assert_eq!(stage, "analyze.sections"),
+                other => panic!("expected a budget failure, got {other:?}"),
+            }
+        }
+    }
+}
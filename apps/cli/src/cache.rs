@@ -1,18 +1,44 @@
 use std::{
+    fs::File,
     hash::{DefaultHasher, Hash, Hasher},
+    io::{self, Read},
     path::{Path, PathBuf},
 };
 
-use crate::provider::Provider;
+use sha2::{Digest, Sha256};
+
+use crate::{provider::Provider, whisper::WhisperOptions};
 
 /// Get the cache directory for a given URL
-pub fn get_cache_dir(url: &str) -> PathBuf {
+pub fn get_cache_dir(root_cache_dir: &Path, url: &str) -> PathBuf {
     let mut hasher = DefaultHasher::new();
     url.hash(&mut hasher);
     let url_hash = hasher.finish();
-    let cache_dir = get_root_cache_dir();
 
-    cache_dir.join(url_hash.to_string())
+    root_cache_dir.join(url_hash.to_string())
+}
+
+/// Get the cache directory for a local file, keyed by a SHA-256 of its content
+///
+/// Renaming or moving a recording keeps its cache; editing it starts a new one.
+pub fn get_file_cache_dir(root_cache_dir: &Path, path: &Path) -> io::Result<PathBuf> {
+    Ok(root_cache_dir.join(sha256_file(path)?))
+}
+
+/// Hex SHA-256 of a file's content, read in 1 MiB blocks
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
 pub fn get_root_cache_dir() -> PathBuf {
@@ -25,22 +51,90 @@ pub fn get_model_dir(cache_dir: &Path) -> PathBuf {
     cache_dir.join("models")
 }
 
-/// Find a video file in the cache directory
+/// Directory of cached LLM responses, shared by all jobs
+pub fn get_llm_cache_dir(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("llm")
+}
+
+/// Find downloaded media in the cache directory
+///
+/// Both full videos and audio-only downloads count; a video is preferred when
+/// both are present since it can serve either purpose.
 pub fn find_video_in_cache(cache_dir: &Path) -> Option<PathBuf> {
     let Ok(entries) = std::fs::read_dir(cache_dir) else {
         return None;
     };
 
+    let mut audio_only = None;
     for entry in entries.flatten() {
         let path = entry.path();
         if let Some(ext) = path.extension() {
             let ext = ext.to_string_lossy().to_lowercase();
-            if matches!(ext.as_str(), "mp4" | "webm" | "mkv" | "mov" | "avi") {
+            let is_media = matches!(
+                ext.as_str(),
+                "mp4" | "webm" | "mkv" | "mov" | "avi" | "m4a" | "opus" | "ogg" | "mp3" | "aac"
+            );
+            if !is_media {
+                continue;
+            }
+            if !is_audio_only(&path) {
                 return Some(path);
             }
+            audio_only.get_or_insert(path);
         }
     }
-    None
+    audio_only
+}
+
+/// Whether a cached download holds audio without a video stream
+///
+/// WebM can be either, so audio-only downloads are told apart by their `audio.*` name.
+pub fn is_audio_only(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(ext.as_str(), "m4a" | "opus" | "ogg" | "mp3" | "aac")
+        || path.file_stem().is_some_and(|stem| stem == "audio")
+}
+
+/// Find subtitles fetched by an earlier run, written as `subs.<lang>.<ext>`
+pub fn find_subtitles_in_cache(cache_dir: &Path) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(cache_dir).ok()?;
+    entries.flatten().map(|e| e.path()).find(|path| {
+        let is_subs = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("subs."));
+        let ext = path.extension().and_then(|e| e.to_str());
+        is_subs && matches!(ext, Some("vtt" | "srv3"))
+    })
+}
+
+/// Find the transcript written most recently, diarized or not and whatever
+/// the whisper options
+pub fn find_transcript_in_cache(cache_dir: &Path) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(cache_dir).ok()?;
+    entries
+        .flatten()
+        .filter(|entry| {
+            let path = entry.path();
+            let is_transcript = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("transcript"));
+            is_transcript && path.is_file() && path.extension().is_some_and(|e| e == "json")
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+/// Get the path of a batch's index, kept outside the per-video directories
+pub fn get_batch_index_path(cache_dir: &Path, batch_id: &uuid::Uuid) -> PathBuf {
+    cache_dir.join("batches").join(format!("{batch_id}.json"))
+}
+
+/// Get the path for the video metadata looked up before downloading
+pub fn get_metadata_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("metadata.json")
 }
 
 /// Get the path for a cached audio file
@@ -48,17 +142,79 @@ pub fn get_audio_path(cache_dir: &Path) -> PathBuf {
     cache_dir.join("audio.wav")
 }
 
-/// Get the path for a cached transcript file
-pub fn get_transcript_path(cache_dir: &Path) -> PathBuf {
-    cache_dir.join("transcript.json")
+/// Get the path for a cached transcript file (whisper options aware)
+pub fn get_transcript_path(cache_dir: &Path, whisper: &WhisperOptions) -> PathBuf {
+    match whisper.cache_key() {
+        Some(key) => cache_dir.join(format!("transcript_{key}.json")),
+        None => cache_dir.join("transcript.json"),
+    }
+}
+
+/// Get the path for a cached diarized transcript (whisper options and speaker count aware)
+pub fn get_speakers_path(
+    cache_dir: &Path,
+    whisper: &WhisperOptions,
+    speakers: Option<usize>,
+) -> PathBuf {
+    let extension = match speakers {
+        Some(count) => format!("speakers-{count}.json"),
+        None => "speakers.json".to_string(),
+    };
+    get_transcript_path(cache_dir, whisper).with_extension(extension)
+}
+
+/// Get the directory holding finished chunks of a transcription in progress
+pub fn get_transcript_chunks_dir(cache_dir: &Path, whisper: &WhisperOptions) -> PathBuf {
+    get_transcript_path(cache_dir, whisper).with_extension("chunks")
 }
 
 /// Get the path for a cached report file (provider and language aware)
 pub fn get_report_path(cache_dir: &Path, provider: &Provider, lang: &str) -> PathBuf {
-    let provider_name = match provider {
+    cache_dir.join(format!("report_{}_{}.json", provider_slug(provider), lang))
+}
+
+/// Get the path for the markdown rendering of a report, next to its JSON
+pub fn get_report_markdown_path(cache_dir: &Path, provider: &Provider, lang: &str) -> PathBuf {
+    get_report_path(cache_dir, provider, lang).with_extension("md")
+}
+
+/// Get the path for the LLM usage ledger stored next to a report
+pub fn get_usage_path(cache_dir: &Path, provider: &Provider, lang: &str) -> PathBuf {
+    cache_dir.join(format!("usage_{}_{}.json", provider_slug(provider), lang))
+}
+
+fn provider_slug(provider: &Provider) -> &'static str {
+    match provider {
         Provider::Grok => "grok",
         Provider::Openai => "openai",
         Provider::Gemini => "gemini",
-    };
-    cache_dir.join(format!("report_{}_{}.json", provider_name, lang))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_audio_only_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(find_video_in_cache(dir.path()), None);
+
+        std::fs::write(dir.path().join("audio.wav"), b"").unwrap();
+        std::fs::write(dir.path().join("audio.webm"), b"").unwrap();
+        let found = find_video_in_cache(dir.path()).unwrap();
+        assert_eq!(found.file_name().unwrap(), "audio.webm");
+        assert!(is_audio_only(&found));
+    }
+
+    #[test]
+    fn prefers_video_over_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("audio.m4a"), b"").unwrap();
+        std::fs::write(dir.path().join("video.webm"), b"").unwrap();
+
+        let found = find_video_in_cache(dir.path()).unwrap();
+        assert_eq!(found.file_name().unwrap(), "video.webm");
+        assert!(!is_audio_only(&found));
+    }
 }
@@ -0,0 +1,432 @@
+use std::collections::HashSet;
+
+use crate::{types::Segment, workers::events::SourceSection};
+
+/// How far from a creator chapter a section may start and still be taken to
+/// start with it; prompt lines cover up to 20 seconds each
+const CHAPTER_SNAP_SECONDS: f64 = 20.0;
+
+/// A window of consecutive transcript segments sent to the LLM in one request
+#[derive(Debug, Clone)]
+pub struct TranscriptChunk {
+    pub index: usize,
+    pub segments: Vec<Segment>,
+    /// Part of the timeline this chunk is authoritative for. Overlapping segments
+    /// shared with a neighbour are split evenly between the two chunks.
+    pub owned_start: f64,
+    pub owned_end: f64,
+}
+
+impl TranscriptChunk {
+    pub fn start(&self) -> f64 {
+        self.segments.first().map(|s| s.start).unwrap_or(0.0)
+    }
+
+    pub fn end(&self) -> f64 {
+        self.segments.last().map(|s| s.end).unwrap_or(0.0)
+    }
+}
+
+/// Split segments into windows of at most `max_tokens`, each one starting with
+/// the last `overlap_seconds` of its predecessor so topics cut at a window edge
+/// are seen whole at least once.
+pub fn chunk_segments(
+    segments: &[Segment],
+    max_tokens: usize,
+    overlap_seconds: f64,
+    cost: impl Fn(&Segment) -> usize,
+) -> Vec<TranscriptChunk> {
+    let mut chunks: Vec<TranscriptChunk> = Vec::new();
+    let mut start = 0;
+
+    while start < segments.len() {
+        let mut tokens = 0;
+        let mut end = start;
+        while end < segments.len() {
+            let seg_tokens = cost(&segments[end]);
+            // always take at least one new segment so oversized ones still make progress
+            if tokens + seg_tokens > max_tokens && end > start {
+                break;
+            }
+            tokens += seg_tokens;
+            end += 1;
+        }
+
+        chunks.push(TranscriptChunk {
+            index: chunks.len(),
+            segments: segments[start..end].to_vec(),
+            owned_start: 0.0,
+            owned_end: 0.0,
+        });
+
+        if end >= segments.len() {
+            break;
+        }
+
+        // rewind into the overlap, but never back to this chunk's start and never
+        // so far that the overlap alone would fill the next window
+        let overlap_from = segments[end - 1].end - overlap_seconds;
+        let mut overlap_tokens = 0;
+        let mut next = end;
+        while next > start + 1 && segments[next - 1].start >= overlap_from {
+            overlap_tokens += cost(&segments[next - 1]);
+            if overlap_tokens > max_tokens / 2 {
+                break;
+            }
+            next -= 1;
+        }
+        start = next;
+    }
+
+    assign_ownership(&mut chunks);
+    chunks
+}
+
+fn assign_ownership(chunks: &mut [TranscriptChunk]) {
+    let bounds: Vec<(f64, f64)> = chunks.iter().map(|c| (c.start(), c.end())).collect();
+
+    for (i, chunk) in chunks.iter_mut().enumerate() {
+        chunk.owned_start = match i {
+            0 => 0.0,
+            _ => (bounds[i].0 + bounds[i - 1].1) / 2.0,
+        };
+        chunk.owned_end = match bounds.get(i + 1) {
+            Some(next) => (next.0 + bounds[i].1) / 2.0,
+            None => bounds[i].1,
+        };
+    }
+}
+
+/// Combine per-chunk sections into one sequential list
+///
+/// Sections are clamped to the owning chunk's range, then the sections on either
+/// side of each chunk boundary are merged when they look like the same topic and
+/// fall in the same creator chapter. Sections starting close to a chapter are
+/// moved to start exactly with it.
+pub fn merge_chunk_sections(
+    results: Vec<(TranscriptChunk, Vec<SourceSection>)>,
+    chapter_starts: &[f64],
+) -> Vec<SourceSection> {
+    let mut merged: Vec<SourceSection> = Vec::new();
+
+    for (chunk_no, (chunk, mut sections)) in results.into_iter().enumerate() {
+        sections.sort_by(|a, b| a.started_at.total_cmp(&b.started_at));
+
+        let mut clamped = sections.into_iter().filter_map(|mut section| {
+            section.started_at = section.started_at.max(chunk.owned_start);
+            section.ended_at = section.ended_at.min(chunk.owned_end);
+            (section.ended_at > section.started_at).then_some(section)
+        });
+
+        if chunk_no > 0
+            && let Some(first) = clamped.next()
+        {
+            match merged.last_mut() {
+                Some(last)
+                    if same_chapter(chapter_starts, last, &first) && same_topic(last, &first) =>
+                {
+                    absorb(last, first)
+                }
+                _ => merged.push(first),
+            }
+        }
+        merged.extend(clamped);
+    }
+
+    let mut sequential = close_gaps(merged, chapter_starts);
+    snap_to_chapters(&mut sequential, chapter_starts);
+    sequential
+}
+
+/// Make sections sequential: gaps left by clamping go to the earlier section,
+/// overlaps to the earlier one too, and a section left with nothing of its own
+/// is absorbed into the one before it, or dropped if that one is in another chapter
+fn close_gaps(sections: Vec<SourceSection>, chapter_starts: &[f64]) -> Vec<SourceSection> {
+    let mut sequential: Vec<SourceSection> = Vec::with_capacity(sections.len());
+    for mut section in sections {
+        let Some(prev) = sequential.last_mut() else {
+            sequential.push(section);
+            continue;
+        };
+        if prev.ended_at < section.started_at {
+            prev.ended_at = section.started_at;
+        }
+        let chapter_kept = same_chapter(chapter_starts, prev, &section);
+        section.started_at = section.started_at.max(prev.ended_at);
+        if section.ended_at <= section.started_at {
+            if chapter_kept {
+                absorb(prev, section);
+            }
+        } else {
+            sequential.push(section);
+        }
+    }
+    sequential
+}
+
+/// Move each section's start onto a nearby chapter start, taking the time
+/// from or giving it to the section before
+fn snap_to_chapters(sections: &mut [SourceSection], chapter_starts: &[f64]) {
+    for i in 1..sections.len() {
+        let (before, after) = sections.split_at_mut(i);
+        let (prev, section) = (&mut before[i - 1], &mut after[0]);
+        let nearest = chapter_starts
+            .iter()
+            .copied()
+            .filter(|&c| c > prev.started_at && c < section.ended_at)
+            .filter(|&c| (c - section.started_at).abs() <= CHAPTER_SNAP_SECONDS)
+            .min_by(|a, b| {
+                (a - section.started_at)
+                    .abs()
+                    .total_cmp(&(b - section.started_at).abs())
+            });
+        if let Some(start) = nearest {
+            section.started_at = start;
+            prev.ended_at = start;
+        }
+    }
+}
+
+/// Whether two sections start in the same chapter, counting a start just
+/// before a chapter as the chapter's
+fn same_chapter(chapter_starts: &[f64], a: &SourceSection, b: &SourceSection) -> bool {
+    let chapter = |at: f64| {
+        chapter_starts
+            .iter()
+            .filter(|&&c| c <= at + CHAPTER_SNAP_SECONDS)
+            .count()
+    };
+    chapter(a.started_at) == chapter(b.started_at)
+}
+
+fn same_topic(a: &SourceSection, b: &SourceSection) -> bool {
+    if normalize(&a.name) == normalize(&b.name) {
+        return true;
+    }
+
+    let a_concepts: HashSet<String> = a.key_concepts.iter().map(|c| normalize(c)).collect();
+    let b_concepts: HashSet<String> = b.key_concepts.iter().map(|c| normalize(c)).collect();
+    let union = a_concepts.union(&b_concepts).count();
+    if union == 0 {
+        return false;
+    }
+
+    let shared = a_concepts.intersection(&b_concepts).count();
+    shared as f64 / union as f64 >= 0.3
+}
+
+fn absorb(into: &mut SourceSection, other: SourceSection) {
+    into.ended_at = into.ended_at.max(other.ended_at);
+    into.content = format!("{} {}", into.content.trim_end(), other.content.trim_start());
+    for concept in other.key_concepts {
+        if !into
+            .key_concepts
+            .iter()
+            .any(|c| normalize(c) == normalize(&concept))
+        {
+            into.key_concepts.push(concept);
+        }
+    }
+    if !other.external_context.is_empty() && other.external_context != into.external_context {
+        into.external_context = format!("{}\n\n{}", into.external_context, other.external_context);
+    }
+    into.summary = format!("{}\n\n{}", into.summary, other.summary);
+}
+
+fn normalize(s: &str) -> String {
+    s.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, end: f64) -> Segment {
+        Segment {
+            start,
+            end,
+            text: format!("at {start}"),
+            words: Vec::new(),
+            speaker: None,
+        }
+    }
+
+    fn seconds(count: usize) -> Vec<Segment> {
+        (0..count)
+            .map(|i| segment(i as f64, i as f64 + 1.0))
+            .collect()
+    }
+
+    fn chunk(owned_start: f64, owned_end: f64) -> TranscriptChunk {
+        TranscriptChunk {
+            index: 0,
+            segments: vec![segment(owned_start, owned_end)],
+            owned_start,
+            owned_end,
+        }
+    }
+
+    fn section(name: &str, started_at: f64, ended_at: f64) -> SourceSection {
+        SourceSection {
+            name: name.to_string(),
+            content: name.to_lowercase(),
+            started_at,
+            ended_at,
+            key_concepts: Vec::new(),
+            external_context: String::new(),
+            summary: format!("About {name}"),
+        }
+    }
+
+    fn ranges(sections: &[SourceSection]) -> Vec<(&str, f64, f64)> {
+        sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.started_at, s.ended_at))
+            .collect()
+    }
+
+    #[test]
+    fn oversized_segments_get_a_chunk_of_their_own() {
+        let segments = seconds(5);
+        let cost = |s: &Segment| if s.start == 2.0 { 100 } else { 10 };
+
+        let chunks = chunk_segments(&segments, 20, 0.0, cost);
+        let starts: Vec<Vec<f64>> = chunks
+            .iter()
+            .map(|c| c.segments.iter().map(|s| s.start).collect())
+            .collect();
+        assert_eq!(starts, vec![vec![0.0, 1.0], vec![2.0], vec![3.0, 4.0]]);
+    }
+
+    #[test]
+    fn overlap_is_capped_at_half_a_window() {
+        let segments = seconds(30);
+        let chunks = chunk_segments(&segments, 10, 1000.0, |_| 1);
+
+        assert!(chunks.len() > 2);
+        for pair in chunks.windows(2) {
+            let shared = pair[1]
+                .segments
+                .iter()
+                .filter(|s| s.start < pair[0].end())
+                .count();
+            assert!((1..=5).contains(&shared), "{shared} segments shared");
+        }
+        assert_eq!(chunks.last().unwrap().end(), 30.0);
+    }
+
+    #[test]
+    fn ownership_splits_overlaps_at_their_middle() {
+        let segments = seconds(30);
+        let chunks = chunk_segments(&segments, 10, 4.0, |_| 1);
+
+        assert_eq!(chunks[0].owned_start, 0.0);
+        assert_eq!(chunks.last().unwrap().owned_end, 30.0);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].owned_end, pair[1].owned_start);
+            assert_eq!(pair[0].owned_end, (pair[1].start() + pair[0].end()) / 2.0);
+        }
+    }
+
+    #[test]
+    fn merges_clamped_sections_across_chunk_boundaries() {
+        let mut second = section("Borrowing", 40.0, 70.0);
+        second.key_concepts = vec!["borrow checker".to_string()];
+        let mut continued = section("References", 45.0, 90.0);
+        continued.key_concepts = vec!["Borrow checker".to_string()];
+
+        let merged = merge_chunk_sections(
+            vec![
+                (
+                    chunk(0.0, 50.0),
+                    vec![section("Ownership", 0.0, 30.0), second],
+                ),
+                (
+                    chunk(50.0, 120.0),
+                    vec![continued, section("Lifetimes", 90.0, 130.0)],
+                ),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            ranges(&merged),
+            vec![
+                ("Ownership", 0.0, 40.0),
+                ("Borrowing", 40.0, 90.0),
+                ("Lifetimes", 90.0, 120.0),
+            ]
+        );
+        assert_eq!(merged[1].content, "borrowing references");
+    }
+
+    #[test]
+    fn absorbs_sections_left_with_an_empty_range() {
+        let merged = merge_chunk_sections(
+            vec![(
+                chunk(0.0, 100.0),
+                vec![
+                    section("Setup", 0.0, 60.0),
+                    section("Aside", 20.0, 40.0),
+                    section("Demo", 60.0, 100.0),
+                ],
+            )],
+            &[],
+        );
+
+        assert_eq!(
+            ranges(&merged),
+            vec![("Setup", 0.0, 60.0), ("Demo", 60.0, 100.0)]
+        );
+        assert_eq!(merged[0].content, "setup aside");
+    }
+
+    #[test]
+    fn sections_follow_creator_chapters() {
+        let chapters = [0.0, 100.0, 200.0];
+        let merged = merge_chunk_sections(
+            vec![
+                (
+                    chunk(0.0, 110.0),
+                    vec![section("Intro", 0.0, 95.0), section("Setup", 95.0, 110.0)],
+                ),
+                (
+                    chunk(110.0, 300.0),
+                    vec![
+                        section("Setup", 110.0, 190.0),
+                        section("Demo", 190.0, 300.0),
+                    ],
+                ),
+            ],
+            &chapters,
+        );
+
+        assert_eq!(
+            ranges(&merged),
+            vec![
+                ("Intro", 0.0, 100.0),
+                ("Setup", 100.0, 200.0),
+                ("Demo", 200.0, 300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn never_merges_across_a_chapter() {
+        let results = || {
+            vec![
+                (chunk(0.0, 125.0), vec![section("Rust", 0.0, 125.0)]),
+                (chunk(125.0, 300.0), vec![section("Rust", 125.0, 300.0)]),
+            ]
+        };
+
+        assert_eq!(
+            ranges(&merge_chunk_sections(results(), &[])),
+            vec![("Rust", 0.0, 300.0)]
+        );
+        assert_eq!(
+            ranges(&merge_chunk_sections(results(), &[0.0, 120.0])),
+            vec![("Rust", 0.0, 120.0), ("Rust", 120.0, 300.0)]
+        );
+    }
+}
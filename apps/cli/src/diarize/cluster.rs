@@ -0,0 +1,122 @@
+/// Merging stops once the closest clusters are less alike than this
+/// (cosine similarity of mean-centred embeddings)
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.2;
+
+/// Group embeddings by average-linkage agglomerative clustering
+///
+/// Merges the two most similar clusters until `count` remain, or, without a
+/// count, until no two clusters are at least `threshold` alike. Returns a
+/// cluster index per embedding, numbered in order of first appearance.
+pub fn cluster(embeddings: &[Vec<f32>], count: Option<usize>, threshold: f32) -> Vec<usize> {
+    let n = embeddings.len();
+    let mut members: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    let mut similarity: Vec<Vec<f32>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| cosine(&embeddings[i], &embeddings[j]))
+                .collect()
+        })
+        .collect();
+    let mut alive: Vec<usize> = (0..n).collect();
+
+    while alive.len() > count.unwrap_or(1).max(1) {
+        let mut best = None;
+        for (x, &a) in alive.iter().enumerate() {
+            for &b in &alive[x + 1..] {
+                if best.is_none_or(|(_, _, s)| similarity[a][b] > s) {
+                    best = Some((a, b, similarity[a][b]));
+                }
+            }
+        }
+        let Some((a, b, s)) = best else { break };
+        if count.is_none() && s < threshold {
+            break;
+        }
+
+        let (size_a, size_b) = (members[a].len() as f32, members[b].len() as f32);
+        for &k in &alive {
+            let merged =
+                (similarity[a][k] * size_a + similarity[b][k] * size_b) / (size_a + size_b);
+            similarity[a][k] = merged;
+            similarity[k][a] = merged;
+        }
+        let moved = std::mem::take(&mut members[b]);
+        members[a].extend(moved);
+        alive.retain(|&c| c != b);
+    }
+
+    let mut labels = vec![0; n];
+    let mut order: Vec<&Vec<usize>> = alive.iter().map(|&c| &members[c]).collect();
+    order.sort_by_key(|m| m.iter().min().copied());
+    for (label, m) in order.into_iter().enumerate() {
+        for &i in m {
+            labels[i] = label;
+        }
+    }
+    labels
+}
+
+/// Index of the centroid `embedding` is most similar to
+pub fn nearest(embedding: &[f32], centroids: &[Vec<f32>]) -> usize {
+    (0..centroids.len())
+        .max_by(|&a, &b| {
+            cosine(embedding, &centroids[a]).total_cmp(&cosine(embedding, &centroids[b]))
+        })
+        .unwrap_or(0)
+}
+
+/// Mean of the embeddings with each label
+pub fn centroids(embeddings: &[Vec<f32>], labels: &[usize]) -> Vec<Vec<f32>> {
+    let count = labels.iter().max().map_or(0, |m| m + 1);
+    (0..count)
+        .map(|label| {
+            let members: Vec<&Vec<f32>> = embeddings
+                .iter()
+                .zip(labels)
+                .filter(|(_, l)| **l == label)
+                .map(|(e, _)| e)
+                .collect();
+            mean(&members)
+        })
+        .collect()
+}
+
+pub fn mean(vectors: &[&Vec<f32>]) -> Vec<f32> {
+    let len = vectors.first().map_or(0, |v| v.len());
+    let mut sum = vec![0.0; len];
+    for v in vectors {
+        for (s, x) in sum.iter_mut().zip(v.iter()) {
+            *s += x;
+        }
+    }
+    sum.iter().map(|s| s / vectors.len() as f32).collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot / (norm(a) * norm(b)).max(f32::EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_similar_vectors() {
+        let embeddings = vec![
+            vec![1.0, 0.1],
+            vec![-1.0, 0.0],
+            vec![0.9, 0.0],
+            vec![-0.8, -0.1],
+            vec![0.0, 1.0],
+        ];
+
+        assert_eq!(cluster(&embeddings, None, 0.5), [0, 1, 0, 1, 2]);
+        assert_eq!(cluster(&embeddings, Some(2), 0.5)[..4], [0, 1, 0, 1]);
+        assert_eq!(
+            nearest(&[0.7, 0.2], &centroids(&embeddings, &[0, 1, 0, 1, 2])),
+            0
+        );
+    }
+}
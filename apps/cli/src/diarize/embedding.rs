@@ -0,0 +1,179 @@
+use std::f32::consts::PI;
+
+use crate::whisper::SAMPLE_RATE;
+
+/// 32 ms frames, the usual size for speech features at 16 kHz
+const FFT_SIZE: usize = 512;
+const HOP_SAMPLES: usize = SAMPLE_RATE / 100;
+const MEL_BANDS: usize = 24;
+const MEL_MIN_HZ: f32 = 80.0;
+const MEL_MAX_HZ: f32 = 7_600.0;
+
+/// Frames this far below the loudest one are pauses and don't describe the voice
+const VOICED_DB_BELOW_PEAK: f32 = 30.0;
+
+/// Length of the vectors [`embed_voice`] returns
+pub const EMBEDDING_LEN: usize = MEL_BANDS * 2;
+
+/// A rough voice print of one stretch of speech
+///
+/// Mean and spread of the log-mel spectrum over the voiced frames: cheap,
+/// dependency free and good enough to tell a handful of speakers apart when
+/// their voices differ in pitch and timbre. `None` when there is too little
+/// audio to say anything.
+pub fn embed_voice(samples: &[f32], filterbank: &MelFilterbank) -> Option<Vec<f32>> {
+    let frames = log_mel_frames(samples, filterbank);
+    let levels: Vec<f32> = frames
+        .iter()
+        .map(|f| f.iter().sum::<f32>() / MEL_BANDS as f32)
+        .collect();
+    let peak = levels.iter().copied().fold(f32::MIN, f32::max);
+    let voiced: Vec<&[f32; MEL_BANDS]> = frames
+        .iter()
+        .zip(&levels)
+        .filter(|(_, level)| **level > peak - VOICED_DB_BELOW_PEAK)
+        .map(|(frame, _)| frame)
+        .collect();
+    if voiced.len() < 10 {
+        return None;
+    }
+
+    let n = voiced.len() as f32;
+    let mut embedding = vec![0.0; EMBEDDING_LEN];
+    for band in 0..MEL_BANDS {
+        let mean = voiced.iter().map(|f| f[band]).sum::<f32>() / n;
+        let variance = voiced.iter().map(|f| (f[band] - mean).powi(2)).sum::<f32>() / n;
+        embedding[band] = mean;
+        embedding[MEL_BANDS + band] = variance.sqrt();
+    }
+    Some(embedding)
+}
+
+/// Triangular mel filters over the bins of a [`FFT_SIZE`] spectrum
+pub struct MelFilterbank {
+    /// `(first bin, weights)` per band
+    filters: Vec<(usize, Vec<f32>)>,
+    window: Vec<f32>,
+}
+
+impl Default for MelFilterbank {
+    fn default() -> Self {
+        let hz_to_mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+        let mel_to_hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+        let bin_of = |hz: f32| hz * FFT_SIZE as f32 / SAMPLE_RATE as f32;
+
+        let (low, high) = (hz_to_mel(MEL_MIN_HZ), hz_to_mel(MEL_MAX_HZ));
+        let edges: Vec<f32> = (0..MEL_BANDS + 2)
+            .map(|i| {
+                bin_of(mel_to_hz(
+                    low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32,
+                ))
+            })
+            .collect();
+
+        let filters = edges
+            .windows(3)
+            .map(|e| {
+                let first = e[0].floor() as usize;
+                let weights = (first..=e[2].ceil() as usize)
+                    .map(|bin| {
+                        let bin = bin as f32;
+                        let rising = (bin - e[0]) / (e[1] - e[0]);
+                        let falling = (e[2] - bin) / (e[2] - e[1]);
+                        rising.min(falling).max(0.0)
+                    })
+                    .collect();
+                (first, weights)
+            })
+            .collect();
+
+        let window = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+
+        Self { filters, window }
+    }
+}
+
+/// Log mel energies (dB) of each frame
+fn log_mel_frames(samples: &[f32], filterbank: &MelFilterbank) -> Vec<[f32; MEL_BANDS]> {
+    let mut re = vec![0.0; FFT_SIZE];
+    let mut im = vec![0.0; FFT_SIZE];
+
+    (0..samples.len().saturating_sub(FFT_SIZE - 1))
+        .step_by(HOP_SAMPLES)
+        .map(|start| {
+            for (i, (r, w)) in re.iter_mut().zip(&filterbank.window).enumerate() {
+                *r = samples[start + i] * w;
+            }
+            im.fill(0.0);
+            fft(&mut re, &mut im);
+
+            let mut bands = [0.0; MEL_BANDS];
+            for (band, (first, weights)) in bands.iter_mut().zip(&filterbank.filters) {
+                let power: f32 = weights
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| first + i <= FFT_SIZE / 2)
+                    .map(|(i, w)| w * (re[first + i].powi(2) + im[first + i].powi(2)))
+                    .sum();
+                *band = 10.0 * (power + 1e-10).log10();
+            }
+            bands
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT; `re.len()` must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_finds_the_frequency_of_a_sine() {
+        let bin = 40;
+        let mut re: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| (2.0 * PI * bin as f32 * i as f32 / FFT_SIZE as f32).sin())
+            .collect();
+        let mut im = vec![0.0; FFT_SIZE];
+        fft(&mut re, &mut im);
+
+        let loudest = (0..FFT_SIZE / 2)
+            .max_by(|&a, &b| {
+                let power = |i: usize| re[i].powi(2) + im[i].powi(2);
+                power(a).total_cmp(&power(b))
+            })
+            .unwrap();
+        assert_eq!(loudest, bin);
+    }
+}
@@ -0,0 +1,164 @@
+pub mod cluster;
+pub mod embedding;
+
+pub use cluster::*;
+pub use embedding::*;
+
+use std::path::Path;
+
+use crate::{types::Segment, whisper::SAMPLE_RATE};
+
+/// Shorter segments say too little about a voice to cluster on; they are
+/// matched to the nearest speaker afterwards
+const MIN_CLUSTERED_SECONDS: f64 = 1.0;
+
+/// Clustering is cubic in the number of segments, so only the longest ones
+/// take part in long recordings
+const MAX_CLUSTERED_SEGMENTS: usize = 400;
+
+/// Speaker label for each segment, numbered by first appearance ("Speaker 1")
+///
+/// `speakers` fixes how many speakers there are; without it the count is
+/// guessed from how distinct the voices sound. Segments with too little audio
+/// to tell get `None`.
+pub fn diarize(
+    audio_path: &Path,
+    segments: &[Segment],
+    speakers: Option<usize>,
+) -> Result<Vec<Option<String>>, hound::Error> {
+    let mut reader = hound::WavReader::open(audio_path)?;
+    let total = reader.duration();
+    let filterbank = MelFilterbank::default();
+
+    let mut embeddings = Vec::with_capacity(segments.len());
+    for segment in segments {
+        let start = ((segment.start * SAMPLE_RATE as f64) as u32).min(total);
+        let end = ((segment.end * SAMPLE_RATE as f64) as u32).clamp(start, total);
+        reader.seek(start)?;
+        let samples = reader
+            .samples::<i16>()
+            .take((end - start) as usize)
+            .map(|s| s.map(|s| s as f32 / i16::MAX as f32))
+            .collect::<Result<Vec<f32>, _>>()?;
+        embeddings.push(embed_voice(&samples, &filterbank));
+    }
+
+    // what every voice shares (microphone, room) would otherwise dominate
+    let present: Vec<&Vec<f32>> = embeddings.iter().flatten().collect();
+    if present.is_empty() {
+        return Ok(vec![None; segments.len()]);
+    }
+    let shared = mean(&present);
+    let embeddings: Vec<Option<Vec<f32>>> = embeddings
+        .into_iter()
+        .map(|e| e.map(|e| e.iter().zip(&shared).map(|(x, m)| x - m).collect()))
+        .collect();
+
+    let duration = |i: usize| segments[i].end - segments[i].start;
+    let placeable: Vec<usize> = (0..segments.len())
+        .filter(|&i| embeddings[i].is_some())
+        .collect();
+    let mut clustered: Vec<usize> = placeable
+        .iter()
+        .copied()
+        .filter(|&i| duration(i) >= MIN_CLUSTERED_SECONDS)
+        .collect();
+    if clustered.is_empty() {
+        clustered = placeable;
+    }
+    if clustered.len() > MAX_CLUSTERED_SEGMENTS {
+        clustered.sort_by(|&a, &b| duration(b).total_cmp(&duration(a)));
+        clustered.truncate(MAX_CLUSTERED_SEGMENTS);
+        clustered.sort();
+    }
+
+    let chosen: Vec<Vec<f32>> = clustered
+        .iter()
+        .filter_map(|&i| embeddings[i].clone())
+        .collect();
+    let labels = cluster(&chosen, speakers, DEFAULT_SIMILARITY_THRESHOLD);
+    let centroids = centroids(&chosen, &labels);
+
+    let mut first_seen = Vec::new();
+    let mut names = Vec::with_capacity(segments.len());
+    for (i, embedding) in embeddings.iter().enumerate() {
+        let label = match clustered.binary_search(&i) {
+            Ok(position) => Some(labels[position]),
+            Err(_) => embedding.as_ref().map(|e| nearest(e, &centroids)),
+        };
+        names.push(label.map(|label| {
+            let number = match first_seen.iter().position(|&l| l == label) {
+                Some(number) => number,
+                None => {
+                    first_seen.push(label);
+                    first_seen.len() - 1
+                }
+            };
+            format!("Speaker {}", number + 1)
+        }));
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    /// Harmonics of `pitch`, each `tilt` times quieter than the one below
+    fn voice(pitch: f32, tilt: f32, seconds: usize) -> impl Iterator<Item = f32> {
+        (0..seconds * SAMPLE_RATE).map(move |i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            (1..12)
+                .map(|k| tilt.powi(k) * (2.0 * PI * pitch * k as f32 * t).sin())
+                .sum::<f32>()
+                * 0.1
+        })
+    }
+
+    #[test]
+    fn tells_two_voices_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audio.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        let mut segments = Vec::new();
+        for turn in 0..5 {
+            let sample = if turn % 2 == 0 {
+                voice(110.0, 0.6, 3).collect::<Vec<_>>()
+            } else {
+                voice(240.0, 0.95, 3).collect()
+            };
+            for s in sample {
+                writer.write_sample((s * i16::MAX as f32) as i16).unwrap();
+            }
+            segments.push(Segment {
+                start: turn as f64 * 3.0,
+                end: turn as f64 * 3.0 + 3.0,
+                text: String::new(),
+                words: Vec::new(),
+                speaker: None,
+            });
+        }
+        writer.finalize().unwrap();
+
+        let speakers = diarize(&path, &segments, None).unwrap();
+        let speakers: Vec<&str> = speakers.iter().map(|s| s.as_deref().unwrap()).collect();
+        assert_eq!(
+            speakers,
+            [
+                "Speaker 1",
+                "Speaker 2",
+                "Speaker 1",
+                "Speaker 2",
+                "Speaker 1"
+            ]
+        );
+    }
+}
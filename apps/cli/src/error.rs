@@ -29,9 +29,6 @@ pub enum BratishkaError {
     #[error("Missing API key: {env_var} environment variable is not set")]
     MissingApiKey { env_var: String },
 
-    #[error("Model download failed: {url}: {reason}")]
-    ModelDownloadFailed { url: String, reason: String },
-
     #[error("Failed to process sections: {0}")]
     ProcessSectionsFailed(#[from] InteligenceError),
 
@@ -0,0 +1,362 @@
+use std::path::Path;
+
+use crate::{
+    cache::{
+        find_subtitles_in_cache, find_transcript_in_cache, get_cache_dir, get_file_cache_dir,
+        get_root_cache_dir,
+    },
+    media::load_subtitles,
+    timestamp::format_subtitle_timestamp,
+    types::{Segment, Transcript},
+    workers::events::MediaSource,
+};
+
+/// Characters per subtitle line, the usual broadcast limit
+const MAX_LINE_CHARS: usize = 42;
+
+/// Lines of a subtitle on screen at once
+const MAX_LINES: usize = 2;
+
+/// Longest a subtitle stays up; longer segments are split between words
+const MAX_CUE_SECONDS: f64 = 7.0;
+
+/// File formats a transcript can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Srt,
+    Vtt,
+    Txt,
+    Tsv,
+    Json,
+}
+
+impl TranscriptFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Txt => "txt",
+            Self::Tsv => "tsv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// One subtitle: what is on screen and when
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub lines: Vec<String>,
+}
+
+/// A word with its time, taken from whisper or estimated from the segment
+struct TimedWord {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+/// The transcript of the job run on `input`, a URL or media file, or the
+/// transcript JSON file itself
+///
+/// Whisper transcripts are preferred over subtitles; of several (diarized,
+/// other decoding options) the most recent one wins.
+pub fn load_job_transcript(input: &str) -> anyhow::Result<Transcript> {
+    let path = Path::new(input);
+    if path.is_file() && path.extension().is_some_and(|e| e == "json") {
+        return Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?);
+    }
+
+    let root_cache_dir = get_root_cache_dir();
+    let cache_dir = match MediaSource::detect(input)? {
+        MediaSource::Url(url) => get_cache_dir(&root_cache_dir, &url),
+        MediaSource::LocalFile(path) => get_file_cache_dir(&root_cache_dir, &path)?,
+    };
+    if let Some(path) = find_transcript_in_cache(&cache_dir) {
+        return Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?);
+    }
+    if let Some(path) = find_subtitles_in_cache(&cache_dir) {
+        return load_subtitles(&path);
+    }
+    anyhow::bail!("no transcript for {input} yet; run `bratishka {input}` first")
+}
+
+/// Render `transcript` in `format`
+pub fn export_transcript(
+    transcript: &Transcript,
+    format: TranscriptFormat,
+) -> anyhow::Result<String> {
+    Ok(match format {
+        TranscriptFormat::Srt => format_srt(&subtitle_cues(&transcript.segments)),
+        TranscriptFormat::Vtt => format_vtt(&subtitle_cues(&transcript.segments)),
+        TranscriptFormat::Txt => format_txt(&transcript.segments),
+        TranscriptFormat::Tsv => format_tsv(&transcript.segments),
+        TranscriptFormat::Json => serde_json::to_string_pretty(transcript)? + "\n",
+    })
+}
+
+/// Cut segments into subtitles that fit on screen and stay up no longer
+/// than [`MAX_CUE_SECONDS`]
+///
+/// Splits fall between words, timed from whisper's word timestamps when the
+/// segment has them and spread over the segment by length otherwise. A
+/// diarized segment starts with its speaker's name.
+pub fn subtitle_cues(segments: &[Segment]) -> Vec<Cue> {
+    let mut cues: Vec<Cue> = Vec::new();
+    for segment in segments {
+        let mut words = timed_words(segment);
+        if let (Some(speaker), Some(first)) = (&segment.speaker, words.first_mut()) {
+            first.text = format!("{speaker}: {}", first.text);
+        }
+
+        let mut pending: Vec<TimedWord> = Vec::new();
+        for word in words {
+            if let Some(first) = pending.first() {
+                let texts: Vec<&str> = pending
+                    .iter()
+                    .chain([&word])
+                    .map(|w| w.text.as_str())
+                    .collect();
+                if word.end - first.start > MAX_CUE_SECONDS || wrap_lines(&texts).len() > MAX_LINES
+                {
+                    cues.push(cue_from(&pending));
+                    pending.clear();
+                }
+            }
+            pending.push(word);
+        }
+        if !pending.is_empty() {
+            cues.push(cue_from(&pending));
+        }
+    }
+
+    // neighbouring whisper segments can overlap by a few centiseconds
+    for i in 1..cues.len() {
+        let next_start = cues[i].start;
+        let cue = &mut cues[i - 1];
+        cue.end = cue.end.min(next_start).max(cue.start);
+    }
+    cues
+}
+
+fn timed_words(segment: &Segment) -> Vec<TimedWord> {
+    if !segment.words.is_empty() {
+        return segment
+            .words
+            .iter()
+            .filter(|w| !w.text.trim().is_empty())
+            .map(|w| TimedWord {
+                start: w.start,
+                end: w.end,
+                text: w.text.trim().to_string(),
+            })
+            .collect();
+    }
+
+    let texts: Vec<&str> = segment.text.split_whitespace().collect();
+    let total_chars: usize = texts.iter().map(|t| t.chars().count()).sum();
+    let per_char = (segment.end - segment.start) / total_chars.max(1) as f64;
+    let mut at = segment.start;
+    texts
+        .into_iter()
+        .map(|text| {
+            let start = at;
+            at += per_char * text.chars().count() as f64;
+            TimedWord {
+                start,
+                end: at,
+                text: text.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn cue_from(words: &[TimedWord]) -> Cue {
+    let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+    Cue {
+        start: words[0].start,
+        end: words[words.len() - 1].end,
+        lines: wrap_lines(&texts),
+    }
+}
+
+/// Break words into lines of at most [`MAX_LINE_CHARS`]
+///
+/// Text that fits on two lines is split where they come out closest in
+/// length, which reads better than a full line over a stub.
+fn wrap_lines(words: &[&str]) -> Vec<String> {
+    let text = words.join(" ");
+    if text.chars().count() <= MAX_LINE_CHARS {
+        return vec![text];
+    }
+
+    let balanced = (1..words.len())
+        .map(|i| (words[..i].join(" "), words[i..].join(" ")))
+        .map(|(top, bottom)| {
+            let widest = top.chars().count().max(bottom.chars().count());
+            (widest, top, bottom)
+        })
+        .filter(|(widest, _, _)| *widest <= MAX_LINE_CHARS)
+        .min_by_key(|(widest, _, _)| *widest);
+    if let Some((_, top, bottom)) = balanced {
+        return vec![top, bottom];
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= MAX_LINE_CHARS => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+fn format_srt(cues: &[Cue]) -> String {
+    let mut output = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        output.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_subtitle_timestamp(cue.start, ','),
+            format_subtitle_timestamp(cue.end, ','),
+            cue.lines.join("\n")
+        ));
+    }
+    output
+}
+
+fn format_vtt(cues: &[Cue]) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for cue in cues {
+        // cue text is markup; a bare `<` or `&` would be read as a tag or entity
+        let text = cue
+            .lines
+            .join("\n")
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        output.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_subtitle_timestamp(cue.start, '.'),
+            format_subtitle_timestamp(cue.end, '.'),
+            text
+        ));
+    }
+    output
+}
+
+/// One segment per line, the speaker in front when diarized
+fn format_txt(segments: &[Segment]) -> String {
+    let mut output = String::new();
+    for segment in segments {
+        if let Some(speaker) = &segment.speaker {
+            output.push_str(&format!("{speaker}: "));
+        }
+        output.push_str(segment.text.trim());
+        output.push('\n');
+    }
+    output
+}
+
+/// Whisper's own TSV layout: start and end in milliseconds, then the text;
+/// diarized transcripts get a speaker column before the text
+fn format_tsv(segments: &[Segment]) -> String {
+    let diarized = segments.iter().any(|s| s.speaker.is_some());
+    let mut output = String::from(if diarized {
+        "start\tend\tspeaker\ttext\n"
+    } else {
+        "start\tend\ttext\n"
+    });
+    let field = |text: &str| text.trim().replace(['\t', '\n', '\r'], " ");
+    for segment in segments {
+        output.push_str(&format!(
+            "{}\t{}\t",
+            (segment.start * 1000.0).round() as u64,
+            (segment.end * 1000.0).round() as u64
+        ));
+        if diarized {
+            output.push_str(&field(segment.speaker.as_deref().unwrap_or_default()));
+            output.push('\t');
+        }
+        output.push_str(&field(&segment.text));
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, end: f64, text: &str) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            words: Vec::new(),
+            speaker: None,
+        }
+    }
+
+    fn transcript(segments: Vec<Segment>) -> Transcript {
+        Transcript {
+            text: segments.iter().map(|s| s.text.as_str()).collect(),
+            segments,
+            language: "en".to_string(),
+            model: None,
+            device: None,
+            non_speech: Vec::new(),
+            filtered: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn writes_srt_with_wrapped_and_split_cues() {
+        let transcript = transcript(vec![
+            segment(0.0, 2.5, " Hello there."),
+            segment(
+                3661.0,
+                3673.0,
+                " Ownership means every value has a single owner, and when the owner goes \
+                 out of scope the value is dropped.",
+            ),
+        ]);
+
+        let srt = export_transcript(&transcript, TranscriptFormat::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:02,500\nHello there.\n\n\
+             2\n01:01:01,000 --> 01:01:07,897\nOwnership means every value has\na single owner, and when the\n\n\
+             3\n01:01:07,897 --> 01:01:13,000\nowner goes out of scope\nthe value is dropped.\n\n"
+        );
+    }
+
+    #[test]
+    fn writes_vtt_with_escaped_text_and_speakers() {
+        let mut first = segment(0.0, 1.0, " Is a < b?");
+        first.speaker = Some("Speaker 1".to_string());
+        let mut second = segment(1.0, 2.0, " Yes & no.");
+        second.speaker = Some("Speaker 2".to_string());
+
+        let vtt =
+            export_transcript(&transcript(vec![first, second]), TranscriptFormat::Vtt).unwrap();
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n\
+             00:00:00.000 --> 00:00:01.000\nSpeaker 1: Is a &lt; b?\n\n\
+             00:00:01.000 --> 00:00:02.000\nSpeaker 2: Yes &amp; no.\n\n"
+        );
+
+        let tsv = export_transcript(
+            &transcript(vec![segment(0.5, 1.25, " One\ttwo")]),
+            TranscriptFormat::Tsv,
+        )
+        .unwrap();
+        assert_eq!(tsv, "start\tend\ttext\n500\t1250\tOne two\n");
+    }
+}
@@ -1,33 +1,56 @@
-use crate::types::{Transcript, VideoReport};
+use console::style;
 
-/// Format seconds as MM:SS timestamp
-pub fn format_timestamp(seconds: f64) -> String {
-    let mins = (seconds / 60.0) as u32;
-    let secs = (seconds % 60.0) as u32;
-    format!("{:02}:{:02}", mins, secs)
-}
-
-/// Format transcript segments with timestamps
-pub fn format_transcript_with_timestamps(transcript: &Transcript) -> String {
-    transcript
-        .segments
-        .iter()
-        .map(|seg| format!("[{}] {}", format_timestamp(seg.start), seg.text.trim()))
-        .collect::<Vec<_>>()
-        .join("\n")
-}
+use crate::{
+    batch::{RunEntry, RunStatus},
+    llm::JobUsage,
+    media::{VideoMetadata, youtube_video_id},
+    models::{DEFAULT_MODEL, InstalledModel, ModelSpec},
+    timestamp::format_timestamp,
+    types::VideoReport,
+    workers::events::BatchIndex,
+};
 
-pub fn format_report_readable(report: &VideoReport) -> String {
+/// The report as a markdown document, with the video's details up top
+///
+/// For YouTube videos every section heading links to the moment it starts.
+pub fn format_report_markdown(
+    report: &VideoReport,
+    metadata: Option<&VideoMetadata>,
+    video_url: Option<&str>,
+) -> String {
+    let video_id = video_url.and_then(youtube_video_id);
     let mut output = String::new();
     output.push_str(&format!("# {}\n\n", report.title));
+
+    let mut details = Vec::new();
+    if let Some(metadata) = metadata {
+        if let Some(title) = metadata.title.as_ref().filter(|t| **t != report.title) {
+            details.push(format!("**Video:** {title}"));
+        }
+        if let Some(uploader) = &metadata.uploader {
+            details.push(format!("**Channel:** {uploader}"));
+        }
+        if let Some(date) = &metadata.upload_date {
+            details.push(format!("**Uploaded:** {}", format_upload_date(date)));
+        }
+    }
+    if let Some(url) = video_url {
+        details.push(format!("**Link:** [{url}]({url})"));
+    }
+    if !details.is_empty() {
+        output.push_str(&format!("{}\n\n", details.join(" | ")));
+    }
     output.push_str(&format!(
         "**Duration:** {:.0} minutes | **Difficulty:** {} | **Language:** {}\n\n",
         report.duration_minutes, report.difficulty, report.language
     ));
+    if let Some(metadata) = metadata.filter(|m| !m.tags.is_empty()) {
+        output.push_str(&format!("**Tags:** {}\n\n", metadata.tags.join(", ")));
+    }
 
     output.push_str("## Key takeaways\n\n");
     for topic in &report.key_takeaways {
-        output.push_str(&format!("• {}\n", topic));
+        output.push_str(&format!("- {}\n", topic));
     }
     output.push('\n');
 
@@ -39,11 +62,330 @@ pub fn format_report_readable(report: &VideoReport) -> String {
     for chapter in &report.sections {
         let start = format_timestamp(chapter.start_seconds);
         let end = format_timestamp(chapter.end_seconds);
-        output.push_str(&format!("### [{}–{}] {}\n\n", start, end, chapter.title));
+        let span = match &video_id {
+            Some(id) => format!(
+                "[{start}–{end}](https://youtu.be/{id}?t={})",
+                chapter.start_seconds.max(0.0) as u64
+            ),
+            None => format!("[{start}–{end}]"),
+        };
+        output.push_str(&format!("### {} {}\n\n", span, chapter.title));
         output.push_str(&format!("{}\n\n", chapter.summary));
     }
 
-    output.push('\n');
+    output
+}
+
+/// yt-dlp's `YYYYMMDD` as `YYYY-MM-DD`
+fn format_upload_date(date: &str) -> String {
+    match (date.get(..4), date.get(4..6), date.get(6..)) {
+        (Some(year), Some(month), Some(day)) if date.len() == 8 => {
+            format!("{year}-{month}-{day}")
+        }
+        _ => date.to_string(),
+    }
+}
+
+/// A markdown report styled for the terminal: headings in bold, links
+/// reduced to their text
+pub fn format_markdown_for_terminal(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| {
+            if let Some(title) = line.strip_prefix("# ") {
+                style(render_inline(title)).bold().cyan().to_string()
+            } else if let Some(heading) = line.strip_prefix("## ") {
+                style(render_inline(heading))
+                    .bold()
+                    .underlined()
+                    .to_string()
+            } else if let Some(heading) = line.strip_prefix("### ") {
+                style(render_inline(heading)).bold().to_string()
+            } else if let Some(item) = line.strip_prefix("- ") {
+                format!("  • {}", render_inline(item))
+            } else {
+                render_inline(line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `[text](url)` as its text and `**text**` in bold
+fn render_inline(text: &str) -> String {
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let after = &rest[open + 1..];
+        let Some(label_end) = after.find(']') else {
+            break;
+        };
+        let target = after[label_end + 1..]
+            .strip_prefix('(')
+            .and_then(|target| target.find(')').map(|close| &target[close + 1..]));
+        match target {
+            Some(tail) => {
+                plain.push_str(&rest[..open]);
+                plain.push_str(&after[..label_end]);
+                rest = tail;
+            }
+            None => {
+                plain.push_str(&rest[..open + 1]);
+                rest = after;
+            }
+        }
+    }
+    plain.push_str(rest);
+
+    plain
+        .split("**")
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                style(part).bold().to_string()
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
+
+pub fn format_usage_table(usage: &JobUsage) -> String {
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:<18} {:<16} {:>10} {:>10} {:>9} {:>10}\n",
+        "Stage", "Model", "Prompt", "Completion", "Latency", "Cost"
+    ));
+
+    for call in &usage.calls {
+        let marker = if call.estimated { "~" } else { "" };
+        let cost = if call.cached {
+            "cached".to_string()
+        } else {
+            format!("${:.4}", call.cost_usd)
+        };
+        output.push_str(&format!(
+            "{:<18} {:<16} {:>10} {:>10} {:>8.1}s {:>10}\n",
+            call.stage,
+            call.model,
+            format!("{marker}{}", call.prompt_tokens),
+            format!("{marker}{}", call.completion_tokens),
+            call.latency_ms as f64 / 1000.0,
+            cost,
+        ));
+    }
+
+    output.push_str(&format!(
+        "{:<18} {:<16} {:>10} {:>10} {:>8.1}s {:>10}\n",
+        "Total",
+        format!("{} calls", usage.calls.len()),
+        usage.total_prompt_tokens(),
+        usage.total_completion_tokens(),
+        usage.total_latency_ms() as f64 / 1000.0,
+        format!("${:.4}", usage.total_cost_usd()),
+    ));
+
+    output
+}
+
+pub fn format_batch_table(index: &BatchIndex) -> String {
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:<10} {:<10} {:<48} {:>10}\n",
+        "Status", "Uploaded", "Title", "Cost"
+    ));
+
+    for entry in &index.entries {
+        let status = if entry.report_path.is_some() {
+            "done"
+        } else {
+            "failed"
+        };
+        let title = entry
+            .report_title
+            .as_deref()
+            .or(entry.title.as_deref())
+            .unwrap_or(&entry.url);
+        output.push_str(&format!(
+            "{:<10} {:<10} {:<48} {:>10}\n",
+            status,
+            entry.upload_date.as_deref().unwrap_or("-"),
+            title.chars().take(48).collect::<String>(),
+            format!("${:.4}", entry.cost_usd),
+        ));
+    }
+
+    output.push_str(&format!(
+        "{:<10} {:<10} {:<48} {:>10}\n",
+        "Total",
+        "",
+        format!("{}/{} reports", index.completed(), index.entries.len()),
+        format!("${:.4}", index.total_cost_usd()),
+    ));
+
+    output
+}
+
+pub fn format_run_summary(entries: &[RunEntry]) -> String {
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:<8} {:<40} {:<48} {:>10}\n",
+        "Status", "Input", "Result", "Cost"
+    ));
+
+    let mut total_cost = 0.0;
+    let mut cache_hits = 0;
+    for entry in entries {
+        let (status, result, cost) = match &entry.status {
+            RunStatus::Report {
+                title, cost_usd, ..
+            } => {
+                total_cost += cost_usd;
+                ("done", title.clone(), format!("${cost_usd:.4}"))
+            }
+            RunStatus::Playlist {
+                completed, total, ..
+            } => (
+                "batch",
+                format!("{completed}/{total} reports"),
+                "-".to_string(),
+            ),
+            RunStatus::Failed { stage, message } => {
+                ("failed", format!("{stage}: {message}"), "-".to_string())
+            }
+        };
+        cache_hits += entry.cache_hits.len();
+        output.push_str(&format!(
+            "{:<8} {:<40} {:<48} {:>10}\n",
+            status,
+            entry.input.chars().take(40).collect::<String>(),
+            result.chars().take(48).collect::<String>(),
+            cost,
+        ));
+        if !entry.cache_hits.is_empty() {
+            output.push_str(&format!(
+                "{:<8} {:<40} cached: {}\n",
+                "",
+                "",
+                entry.cache_hits.join(", ")
+            ));
+        }
+    }
+
+    let failed = entries.iter().filter(|e| e.is_failed()).count();
+    output.push_str(&format!(
+        "{:<8} {:<40} {:<48} {:>10}\n",
+        "Total",
+        format!("{} ok, {failed} failed", entries.len() - failed),
+        format!("{cache_hits} cache hits"),
+        format!("${total_cost:.4}"),
+    ));
 
     output
 }
+
+pub fn format_models_table(models: &[ModelSpec], installed: &[InstalledModel]) -> String {
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:<22} {:>9} {:<12} {:<10}\n",
+        "Model", "Size", "Language", "Status"
+    ));
+
+    for spec in models {
+        let status = match installed.iter().find(|m| m.spec == spec) {
+            Some(m) if m.size_bytes > 0 && m.verified => "verified",
+            Some(m) if m.size_bytes > 0 => "installed",
+            Some(m) if m.partial => "partial",
+            _ => "-",
+        };
+        let name = if spec.name == DEFAULT_MODEL {
+            format!("{} (default)", spec.name)
+        } else {
+            spec.name.to_string()
+        };
+        output.push_str(&format!(
+            "{:<22} {:>9} {:<12} {:<10}\n",
+            name,
+            format!("~{} MB", spec.size_mb),
+            if spec.is_english_only() {
+                "English"
+            } else {
+                "multilingual"
+            },
+            status,
+        ));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Section;
+
+    fn report() -> VideoReport {
+        VideoReport {
+            title: "Rust ownership".to_string(),
+            summary: "Who frees what.".to_string(),
+            duration_minutes: 62.0,
+            language: "English".to_string(),
+            difficulty: "Moderate".to_string(),
+            key_takeaways: vec!["Values have one owner".to_string()],
+            sections: vec![Section {
+                start_seconds: 3725.4,
+                end_seconds: 3780.0,
+                title: "Borrowing".to_string(),
+                summary: "References without moves.".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn links_sections_of_youtube_videos() {
+        let url = "https://www.youtube.com/watch?v=abc123";
+        let markdown = format_report_markdown(&report(), None, Some(url));
+        assert!(markdown.contains(&format!("**Link:** [{url}]({url})")));
+        assert!(
+            markdown.contains("### [01:02:05–01:03:00](https://youtu.be/abc123?t=3725) Borrowing")
+        );
+
+        let local = format_report_markdown(&report(), None, None);
+        assert!(!local.contains("**Link:**"));
+        assert!(local.contains("### [01:02:05–01:03:00] Borrowing"));
+    }
+
+    #[test]
+    fn lists_video_details_without_repeating_the_title() {
+        let mut metadata = VideoMetadata {
+            title: Some("Rust ownership".to_string()),
+            uploader: Some("Ferris".to_string()),
+            upload_date: Some("20240131".to_string()),
+            tags: vec!["rust".to_string(), "memory".to_string()],
+            ..Default::default()
+        };
+        let markdown = format_report_markdown(&report(), Some(&metadata), None);
+        assert!(markdown.contains("\n\n**Channel:** Ferris | **Uploaded:** 2024-01-31\n\n"));
+        assert!(markdown.contains("**Tags:** rust, memory\n\n"));
+
+        metadata.title = Some("Ownership in 60 minutes".to_string());
+        let markdown = format_report_markdown(&report(), Some(&metadata), None);
+        assert!(markdown.contains("**Video:** Ownership in 60 minutes | **Channel:** Ferris"));
+        assert_eq!(format_upload_date("2024"), "2024");
+    }
+
+    #[test]
+    fn renders_links_and_bold_but_leaves_broken_markup() {
+        assert_eq!(
+            render_inline("see [the docs](https://doc.rust-lang.org)."),
+            "see the docs."
+        );
+        assert_eq!(render_inline("see [the docs"), "see [the docs");
+        assert_eq!(render_inline("[label](https://x"), "[label](https://x");
+        assert_eq!(render_inline("[a] and [b](c)"), "[a] and b");
+        assert_eq!(
+            render_inline("a **bold** move"),
+            format!("a {} move", style("bold").bold())
+        );
+    }
+}
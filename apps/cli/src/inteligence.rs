@@ -8,7 +8,7 @@ use crate::{
 static SECTIONS_ANALYSIS_PROMPT: &str = r#"
   You are a video content analyzer. You have access to web search to enrich your analysis.
 
-  INPUT: Video transcript with timestamps in format [MM:SS] text
+  INPUT: Video transcript with timestamps in format [MM:SS] text ([HH:MM:SS] past the first hour)
 
   TASK:
   1. Identify logical sections based on topic changes
@@ -82,7 +82,7 @@ pub async fn analyze_sections(
     );
 
     let response = reqwest::Client::new()
-        .post(config.api_url)
+        .post(config.api_url(None))
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", api_key))
         .json(&serde_json::json!({
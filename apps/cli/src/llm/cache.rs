@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::cache::get_llm_cache_dir;
+
+/// Content-addressed store of raw provider responses
+///
+/// Keys hash the provider, endpoint and the full request body (model, messages,
+/// temperature, tools, schema), so any change to the prompt is a cache miss.
+#[derive(Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(root_cache_dir: &Path) -> Self {
+        Self {
+            dir: get_llm_cache_dir(root_cache_dir),
+        }
+    }
+
+    pub fn key(provider: &str, api_url: &str, body: &serde_json::Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(provider.as_bytes());
+        hasher.update([0]);
+        hasher.update(api_url.as_bytes());
+        hasher.update([0]);
+        // serde_json maps are ordered, so equal bodies serialize identically
+        hasher.update(body.to_string().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub async fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let content = fs::read_to_string(self.path(key)).await.ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub async fn put(&self, key: &str, response: &serde_json::Value) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        // write-then-rename so concurrent readers never see a partial file
+        let tmp_path = self.dir.join(format!("{key}.{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&tmp_path, serde_json::to_vec(response)?).await?;
+        fs::rename(&tmp_path, self.path(key)).await
+    }
+
+    pub async fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key)).await;
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        llm::{ChatMessage, JobUsage, LlmClient, UsageTracker},
+        provider::Provider,
+        testing::{MockLlmServer, MockReply},
+    };
+
+    const API_URL: &str = "https://api.x.ai/v1/responses";
+
+    fn body(model: &str, prompt: &str, temperature: f64) -> serde_json::Value {
+        serde_json::json!({
+            "model": model,
+            "input": [{ "role": "user", "content": prompt }],
+            "temperature": temperature,
+        })
+    }
+
+    #[test]
+    fn keys_follow_every_part_of_the_request() {
+        let key = ResponseCache::key("Grok", API_URL, &body("grok-4", "hello", 0.3));
+        assert_eq!(
+            key,
+            ResponseCache::key("Grok", API_URL, &body("grok-4", "hello", 0.3))
+        );
+        assert_eq!(key.len(), 64);
+
+        for other in [
+            ResponseCache::key("OpenAI", API_URL, &body("grok-4", "hello", 0.3)),
+            ResponseCache::key(
+                "Grok",
+                "http://127.0.0.1/v1/responses",
+                &body("grok-4", "hello", 0.3),
+            ),
+            ResponseCache::key("Grok", API_URL, &body("grok-3", "hello", 0.3)),
+            ResponseCache::key("Grok", API_URL, &body("grok-4", "hello!", 0.3)),
+            ResponseCache::key("Grok", API_URL, &body("grok-4", "hello", 0.7)),
+        ] {
+            assert_ne!(key, other);
+        }
+    }
+
+    #[tokio::test]
+    async fn forgotten_replies_are_requested_again() {
+        let server = MockLlmServer::start().await;
+        server.script("", vec![MockReply::content("hi")]).await;
+        let root = tempfile::tempdir().unwrap();
+        let tracker = Arc::new(UsageTracker::new(JobUsage::default(), None));
+        let client = LlmClient::new(Provider::Grok, "test", tracker)
+            .with_base_url(Some(server.base_url()))
+            .without_api_key()
+            .with_cache(ResponseCache::new(root.path()));
+        let messages = [ChatMessage::user("hello")];
+
+        let reply = client.send(&messages, 0.0, false, None).await.unwrap();
+        client.send(&messages, 0.0, false, None).await.unwrap();
+        assert_eq!(server.requests().await.len(), 1);
+
+        client.forget(&reply).await;
+        client.send(&messages, 0.0, false, None).await.unwrap();
+        assert_eq!(server.requests().await.len(), 2);
+    }
+}
@@ -0,0 +1,436 @@
+use std::{sync::Arc, time::Instant};
+
+use serde::Serialize;
+
+use crate::{
+    llm::{
+        EXPECTED_COMPLETION_TOKENS, LlmCall, ResponseCache, UsageTracker, estimate_tokens,
+        parse_usage,
+    },
+    provider::{ApiFormat, Provider, ProviderError},
+    workers::events::JobSpec,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LlmError {
+    #[error("Provider error: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("API request failed with status {status}: {body}")]
+    ApiError { status: u16, body: String },
+
+    #[error("Invalid API response: {0}")]
+    InvalidApiResponse(serde_json::Value),
+
+    #[error("Failed to parse JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Invalid JSON Schema for {schema}: {reason}")]
+    InvalidSchema {
+        schema: &'static str,
+        reason: String,
+    },
+
+    #[error(
+        "Budget exceeded: ${spent_usd:.4} spent, next call estimated at ${estimated_usd:.4}, limit is ${max_cost_usd:.4}"
+    )]
+    BudgetExceeded {
+        spent_usd: f64,
+        estimated_usd: f64,
+        max_cost_usd: f64,
+    },
+
+    #[error("{schema} response failed validation after {attempts} attempts: {reason}")]
+    StructuredOutputFailed {
+        schema: &'static str,
+        attempts: usize,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub role: &'static str,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system",
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user",
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: "assistant",
+            content: content.into(),
+        }
+    }
+}
+
+/// A single prompt sent to the provider
+pub struct LlmRequest {
+    pub system: String,
+    pub user: String,
+    pub temperature: f32,
+    pub web_search: bool,
+}
+
+/// Text content of a reply, plus the cache entry it was stored under
+pub struct LlmReply {
+    pub content: String,
+    cache_key: Option<String>,
+}
+
+/// Schema attached to a request as `response_format` / `text.format`
+pub struct ResponseSchema<'a> {
+    pub name: &'static str,
+    pub schema: &'a serde_json::Value,
+}
+
+pub struct LlmClient {
+    http: reqwest::Client,
+    provider: Provider,
+    stage: &'static str,
+    tracker: Arc<UsageTracker>,
+    cache: Option<ResponseCache>,
+    base_url: Option<String>,
+    #[cfg(test)]
+    without_api_key: bool,
+}
+
+impl LlmClient {
+    /// Client for one pipeline `stage`, recording every call into `tracker`
+    pub fn new(provider: Provider, stage: &'static str, tracker: Arc<UsageTracker>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            provider,
+            stage,
+            tracker,
+            cache: None,
+            base_url: None,
+            #[cfg(test)]
+            without_api_key: false,
+        }
+    }
+
+    /// Client for one of `job`'s stages, honouring its endpoint and cache settings
+    pub fn for_job(job: &JobSpec, stage: &'static str, tracker: Arc<UsageTracker>) -> Self {
+        let mut client =
+            Self::new(job.provider.clone(), stage, tracker).with_base_url(job.api_base_url.clone());
+        if job.llm_cache {
+            client = client.with_cache(ResponseCache::new(&job.root_cache_dir));
+        }
+        #[cfg(test)]
+        if job.without_api_key {
+            client = client.without_api_key();
+        }
+        client
+    }
+
+    /// Talk to `base_url` instead of the provider's public endpoint
+    pub fn with_base_url(mut self, base_url: Option<String>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Send requests without credentials, for the mock server in tests
+    #[cfg(test)]
+    pub fn without_api_key(mut self) -> Self {
+        self.without_api_key = true;
+        self
+    }
+
+    /// Serve identical requests from `cache` instead of the provider
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Send one conversation turn and return the raw text content of the reply
+    pub async fn send(
+        &self,
+        messages: &[ChatMessage],
+        temperature: f32,
+        web_search: bool,
+        schema: Option<&ResponseSchema<'_>>,
+    ) -> Result<LlmReply, LlmError> {
+        let config = self.provider.config();
+        let api_url = config.api_url(self.base_url.as_deref());
+        let body = self.request_body(messages, temperature, web_search, schema);
+
+        let cache_key = self
+            .cache
+            .as_ref()
+            .map(|_| ResponseCache::key(self.provider.name(), &api_url, &body));
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key)
+            && let Some(response) = cache.get(key).await
+            && let Some(content) = extract_content(config.api_format, &response)
+        {
+            let (prompt_tokens, completion_tokens) = parse_usage(&response).unwrap_or((0, 0));
+            self.tracker.settle(
+                0.0,
+                Some(LlmCall {
+                    stage: self.stage.to_string(),
+                    provider: self.provider.name().to_string(),
+                    model: config.model.to_string(),
+                    prompt_tokens,
+                    completion_tokens,
+                    estimated: false,
+                    cached: true,
+                    latency_ms: 0,
+                    cost_usd: 0.0,
+                }),
+            );
+            return Ok(LlmReply {
+                content: content.to_string(),
+                cache_key,
+            });
+        }
+
+        let api_key = self.api_key()?;
+
+        let prompt_estimate = estimate_tokens(&body.to_string()) as u64;
+        let reserved_usd = config
+            .pricing
+            .cost_usd(prompt_estimate, EXPECTED_COMPLETION_TOKENS);
+        self.tracker.reserve(reserved_usd)?;
+
+        let started = Instant::now();
+        let result = self.post(&api_url, &api_key, &body).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.tracker.settle(reserved_usd, None);
+                return Err(e);
+            }
+        };
+        let content = extract_content(config.api_format, &response).map(str::to_string);
+
+        let (prompt_tokens, completion_tokens, estimated) = match parse_usage(&response) {
+            Some((prompt, completion)) => (prompt, completion, false),
+            None => (
+                prompt_estimate,
+                content.as_deref().map(estimate_tokens).unwrap_or(0) as u64,
+                true,
+            ),
+        };
+        self.tracker.settle(
+            reserved_usd,
+            Some(LlmCall {
+                stage: self.stage.to_string(),
+                provider: self.provider.name().to_string(),
+                model: config.model.to_string(),
+                prompt_tokens,
+                completion_tokens,
+                estimated,
+                cached: false,
+                latency_ms,
+                cost_usd: config.pricing.cost_usd(prompt_tokens, completion_tokens),
+            }),
+        );
+
+        let Some(content) = content else {
+            return Err(LlmError::InvalidApiResponse(response));
+        };
+
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key)
+            && let Err(e) = cache.put(key, &response).await
+        {
+            println!("could not cache the LLM response ({e}); it will be requested again next run");
+        }
+
+        Ok(LlmReply { content, cache_key })
+    }
+
+    fn api_key(&self) -> Result<String, ProviderError> {
+        #[cfg(test)]
+        if self.without_api_key {
+            return Ok(String::new());
+        }
+        self.provider.validate_api_key()
+    }
+
+    /// Drop a cached reply, e.g. one that turned out not to match the schema
+    pub async fn forget(&self, reply: &LlmReply) {
+        if let (Some(cache), Some(key)) = (&self.cache, &reply.cache_key) {
+            cache.remove(key).await;
+        }
+    }
+
+    async fn post(
+        &self,
+        url: &str,
+        api_key: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, LlmError> {
+        let response = self
+            .http
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(LlmError::ApiError {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        Ok(response.json::<serde_json::Value>().await?)
+    }
+
+    fn request_body(
+        &self,
+        messages: &[ChatMessage],
+        temperature: f32,
+        web_search: bool,
+        schema: Option<&ResponseSchema<'_>>,
+    ) -> serde_json::Value {
+        let config = self.provider.config();
+        let schema = schema.filter(|_| config.supports_structured_output);
+
+        match config.api_format {
+            ApiFormat::Responses => {
+                let mut body = serde_json::json!({
+                    "model": config.model,
+                    "input": messages,
+                    "temperature": temperature,
+                });
+                if web_search {
+                    body["tools"] = serde_json::json!([{"type": "web_search"}]);
+                }
+                if let Some(schema) = schema {
+                    body["text"] = serde_json::json!({
+                        "format": {
+                            "type": "json_schema",
+                            "name": schema.name,
+                            "schema": schema.schema,
+                            "strict": true,
+                        }
+                    });
+                }
+                body
+            }
+            ApiFormat::ChatCompletions => {
+                // chat completions has no hosted web search tool, the prompt still asks
+                // for enrichment and the model falls back to its own knowledge
+                let mut body = serde_json::json!({
+                    "model": config.model,
+                    "messages": messages,
+                    "temperature": temperature,
+                });
+                if let Some(schema) = schema {
+                    body["response_format"] = serde_json::json!({
+                        "type": "json_schema",
+                        "json_schema": {
+                            "name": schema.name,
+                            "schema": schema.schema,
+                            "strict": true,
+                        }
+                    });
+                }
+                body
+            }
+        }
+    }
+}
+
+/// Pull the assistant text out of a provider response
+pub fn extract_content(format: ApiFormat, response: &serde_json::Value) -> Option<&str> {
+    match format {
+        ApiFormat::Responses => response["output"]
+            .as_array()
+            .and_then(|arr| arr.iter().rev().find(|item| item["type"] == "message"))
+            .and_then(|msg| msg["content"].as_array())
+            .and_then(|content| {
+                content
+                    .iter()
+                    .find(|part| part["type"] == "output_text")
+                    .or_else(|| content.first())
+            })
+            .and_then(|part| part["text"].as_str()),
+        ApiFormat::ChatCompletions => response["choices"][0]["message"]["content"].as_str(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        llm::{JobUsage, UsageTracker},
+        testing::{MockLlmServer, MockReply},
+    };
+
+    async fn send(provider: Provider, server: &MockLlmServer) -> Result<LlmReply, LlmError> {
+        let tracker = Arc::new(UsageTracker::new(JobUsage::default(), None));
+        let client = LlmClient::new(provider, "test", tracker)
+            .with_base_url(Some(server.base_url()))
+            .without_api_key();
+        let schema = serde_json::json!({ "type": "object" });
+        client
+            .send(
+                &[ChatMessage::user("hello")],
+                0.0,
+                false,
+                Some(&ResponseSchema {
+                    name: "greeting",
+                    schema: &schema,
+                }),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn each_provider_gets_its_own_api_format() {
+        let server = MockLlmServer::start().await;
+        server.script("", vec![MockReply::content("{}")]).await;
+
+        send(Provider::Grok, &server).await.unwrap();
+        send(Provider::Openai, &server).await.unwrap();
+
+        let requests = server.requests().await;
+        assert_eq!(requests[0].0, "/v1/responses");
+        assert_eq!(requests[0].1["model"], "grok-4-1-fast");
+        assert_eq!(requests[1].0, "/v1/chat/completions");
+        assert_eq!(
+            requests[1].1["response_format"]["json_schema"]["name"],
+            "greeting"
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_errors_keep_their_message() {
+        let server = MockLlmServer::start().await;
+        server
+            .script(
+                "",
+                vec![MockReply::Error(500, "upstream overloaded".to_string())],
+            )
+            .await;
+
+        let err = send(Provider::Grok, &server).await.err().unwrap();
+        assert!(
+            matches!(&err, LlmError::ApiError { status: 500, body } if body.contains("upstream overloaded")),
+            "{err}"
+        );
+    }
+}
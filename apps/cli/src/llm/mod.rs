@@ -0,0 +1,13 @@
+pub mod cache;
+pub mod client;
+pub mod structured;
+pub mod tokens;
+pub mod transcript_input;
+pub mod usage;
+
+pub use cache::*;
+pub use client::*;
+pub use structured::*;
+pub use tokens::*;
+pub use transcript_input::*;
+pub use usage::*;
@@ -0,0 +1,228 @@
+use serde::de::DeserializeOwned;
+
+use crate::llm::{ChatMessage, LlmClient, LlmError, LlmRequest, ResponseSchema};
+
+/// Number of follow-up turns the model gets to fix an invalid response
+pub const MAX_REPAIR_TURNS: usize = 2;
+
+/// Types the LLM is asked to produce directly
+///
+/// Schemas must stay within the strict structured-output subset: every property
+/// listed in `required` and `additionalProperties: false` on every object.
+pub trait JsonSchema {
+    const SCHEMA_NAME: &'static str;
+
+    fn json_schema() -> serde_json::Value;
+}
+
+impl LlmClient {
+    /// Ask for a `T`, validating the reply against `T::json_schema()`
+    ///
+    /// Invalid replies are sent back to the model together with the validation
+    /// error, up to [`MAX_REPAIR_TURNS`] times.
+    pub async fn complete_structured<T>(&self, request: LlmRequest) -> Result<T, LlmError>
+    where
+        T: JsonSchema + DeserializeOwned,
+    {
+        let schema = T::json_schema();
+        let validator =
+            jsonschema::validator_for(&schema).map_err(|e| LlmError::InvalidSchema {
+                schema: T::SCHEMA_NAME,
+                reason: e.to_string(),
+            })?;
+        let response_schema = ResponseSchema {
+            name: T::SCHEMA_NAME,
+            schema: &schema,
+        };
+
+        let mut messages = vec![
+            ChatMessage::system(request.system),
+            ChatMessage::user(request.user),
+        ];
+        let mut last_error = String::new();
+
+        for _ in 0..=MAX_REPAIR_TURNS {
+            let reply = self
+                .send(
+                    &messages,
+                    request.temperature,
+                    request.web_search,
+                    Some(&response_schema),
+                )
+                .await?;
+
+            match parse_structured::<T>(&reply.content, &validator) {
+                Ok(value) => return Ok(value),
+                Err(reason) => {
+                    // a rerun should get a fresh answer, not replay the broken one
+                    self.forget(&reply).await;
+                    messages.push(ChatMessage::assistant(reply.content));
+                    messages.push(ChatMessage::user(repair_prompt(&reason)));
+                    last_error = reason;
+                }
+            }
+        }
+
+        Err(LlmError::StructuredOutputFailed {
+            schema: T::SCHEMA_NAME,
+            attempts: MAX_REPAIR_TURNS + 1,
+            reason: last_error,
+        })
+    }
+}
+
+fn repair_prompt(reason: &str) -> String {
+    format!(
+        "Your previous response could not be used: {reason}\n\
+         Reply again with ONLY the corrected JSON that matches the required schema. \
+         No code fences, no commentary."
+    )
+}
+
+/// Parse and validate model output, returning a human readable reason on failure
+pub fn parse_structured<T: DeserializeOwned>(
+    content: &str,
+    validator: &jsonschema::Validator,
+) -> Result<T, String> {
+    let json = extract_json(content);
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("response is not valid JSON ({e})"))?;
+
+    let errors: Vec<String> = validator
+        .iter_errors(&value)
+        .take(5)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{path}: {e}")
+            }
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("schema validation failed: {}", errors.join("; ")));
+    }
+
+    serde_json::from_value(value).map_err(|e| format!("unexpected JSON shape ({e})"))
+}
+
+/// Strip markdown code fences and surrounding prose from a JSON response
+pub fn extract_json(content: &str) -> &str {
+    let content = content.trim();
+
+    if let Some(fence_start) = content.find("```") {
+        let after_fence = &content[fence_start + 3..];
+        // skip the info string (`json`, `JSON`, ...) up to the end of the line
+        let body_start = after_fence.find('\n').map(|i| i + 1).unwrap_or(0);
+        let body = &after_fence[body_start..];
+        let body_end = body.find("```").unwrap_or(body.len());
+        return body[..body_end].trim();
+    }
+
+    let start = content.find(['{', '[']);
+    let end = content.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        llm::{JobUsage, UsageTracker},
+        provider::Provider,
+        testing::{MockLlmServer, MockReply},
+    };
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Point {
+        x: i64,
+    }
+
+    impl JsonSchema for Point {
+        const SCHEMA_NAME: &'static str = "point";
+
+        fn json_schema() -> serde_json::Value {
+            json!({
+                "type": "object",
+                "properties": { "x": { "type": "integer" } },
+                "required": ["x"],
+                "additionalProperties": false
+            })
+        }
+    }
+
+    fn parse(content: &str) -> Result<Point, String> {
+        let validator = jsonschema::validator_for(&Point::json_schema()).unwrap();
+        parse_structured(content, &validator)
+    }
+
+    #[test]
+    fn strips_code_fences() {
+        let content = "Here you go:\n```json\n{\"x\": 1}\n```\nAnything else?";
+        assert_eq!(extract_json(content), "{\"x\": 1}");
+        assert_eq!(parse(content), Ok(Point { x: 1 }));
+    }
+
+    #[test]
+    fn strips_prose_after_the_document() {
+        let content = "{\"x\": 2}\n\nLet me know if you need more detail.";
+        assert_eq!(extract_json(content), "{\"x\": 2}");
+        assert_eq!(parse(content), Ok(Point { x: 2 }));
+    }
+
+    #[test]
+    fn explains_invalid_documents() {
+        let reason = parse("Sure! Here is the point you asked for.").unwrap_err();
+        assert!(reason.starts_with("response is not valid JSON"), "{reason}");
+
+        let reason = parse("{\"x\": \"one\"}").unwrap_err();
+        assert!(
+            reason.starts_with("schema validation failed: /x: "),
+            "{reason}"
+        );
+        assert!(repair_prompt(&reason).contains(&reason));
+    }
+
+    #[tokio::test]
+    async fn invalid_replies_are_sent_back_for_repair() {
+        let server = MockLlmServer::start().await;
+        server
+            .script(
+                "",
+                vec![
+                    MockReply::content("Sure! Here is the point you asked for."),
+                    MockReply::content("{\"x\": 3}"),
+                ],
+            )
+            .await;
+        let tracker = Arc::new(UsageTracker::new(JobUsage::default(), None));
+        let client = LlmClient::new(Provider::Grok, "test", Arc::clone(&tracker))
+            .with_base_url(Some(server.base_url()))
+            .without_api_key();
+
+        let point: Point = client
+            .complete_structured(LlmRequest {
+                system: "system".to_string(),
+                user: "user".to_string(),
+                temperature: 0.0,
+                web_search: false,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(point, Point { x: 3 });
+        assert_eq!(tracker.usage().calls.len(), 2);
+        let requests = server.requests().await;
+        let repair = requests[1].1["input"].as_array().unwrap();
+        assert_eq!(repair.len(), 4);
+        assert!(repair[3].to_string().contains("response is not valid JSON"));
+    }
+}
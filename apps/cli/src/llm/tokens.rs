@@ -0,0 +1,6 @@
+/// Rough token count for budgeting and chunking (~4 characters per token)
+///
+/// Providers tokenize differently, so this only needs to be in the right ballpark.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
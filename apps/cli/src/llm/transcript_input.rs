@@ -0,0 +1,137 @@
+use crate::{llm::estimate_tokens, timestamp::format_clock, types::Segment};
+
+/// A transcript as the `[HH:MM:SS] text` lines the analysis prompt describes
+///
+/// Far cheaper than the transcript's JSON, which carries every segment's
+/// text twice along with its word timings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptInput {
+    pub text: String,
+    pub lines: usize,
+    /// Estimated prompt tokens for `text`
+    pub tokens: usize,
+}
+
+impl TranscriptInput {
+    /// Render passages, one line each; see [`merge_passages`]
+    pub fn new(passages: &[Segment]) -> Self {
+        let text: String = passages.iter().map(format_prompt_line).collect();
+        Self {
+            tokens: estimate_tokens(&text),
+            lines: passages.len(),
+            text,
+        }
+    }
+}
+
+/// Merge neighbouring segments into passages spanning up to `line_seconds`
+///
+/// Whisper cuts a segment every few seconds, and each one would cost a
+/// timestamp. A passage never crosses a change of speaker, and a segment
+/// longer than `line_seconds` stays a passage of its own.
+pub fn merge_passages(segments: &[Segment], line_seconds: f64) -> Vec<Segment> {
+    let mut passages: Vec<Segment> = Vec::new();
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match passages.last_mut() {
+            Some(passage)
+                if passage.speaker == segment.speaker
+                    && segment.end - passage.start <= line_seconds =>
+            {
+                passage.end = segment.end;
+                passage.text.push(' ');
+                passage.text.push_str(text);
+            }
+            _ => passages.push(Segment {
+                start: segment.start,
+                end: segment.end,
+                text: text.to_string(),
+                words: Vec::new(),
+                speaker: segment.speaker.clone(),
+            }),
+        }
+    }
+    passages
+}
+
+/// `[HH:MM:SS] text`, or `[HH:MM:SS] Speaker 1: text` when diarized
+pub fn format_prompt_line(passage: &Segment) -> String {
+    match &passage.speaker {
+        Some(speaker) => format!(
+            "[{}] {speaker}: {}\n",
+            format_clock(passage.start),
+            passage.text.trim()
+        ),
+        None => format!(
+            "[{}] {}\n",
+            format_clock(passage.start),
+            passage.text.trim()
+        ),
+    }
+}
+
+/// Estimated tokens of a passage's prompt line
+pub fn prompt_line_tokens(passage: &Segment) -> usize {
+    estimate_tokens(&format_prompt_line(passage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, end: f64, text: &str, speaker: Option<&str>) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            words: Vec::new(),
+            speaker: speaker.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn merges_short_segments_into_timestamped_lines() {
+        let segments = [
+            segment(0.0, 4.0, " Welcome back.", None),
+            segment(4.0, 9.5, " Today: lifetimes.", None),
+            segment(
+                9.5,
+                25.0,
+                " Let's start with a borrow that outlives its owner.",
+                None,
+            ),
+            segment(3725.0, 3730.0, " That's all.", None),
+        ];
+
+        let passages = merge_passages(&segments, 20.0);
+        let input = TranscriptInput::new(&passages);
+        assert_eq!(
+            input.text,
+            "[00:00:00] Welcome back. Today: lifetimes.\n\
+             [00:00:09] Let's start with a borrow that outlives its owner.\n\
+             [01:02:05] That's all.\n"
+        );
+        assert_eq!(input.lines, 3);
+        assert_eq!(input.tokens, estimate_tokens(&input.text));
+        assert_eq!((passages[0].start, passages[0].end), (0.0, 9.5));
+    }
+
+    #[test]
+    fn keeps_speakers_on_separate_lines() {
+        let segments = [
+            segment(0.0, 2.0, " So what is Rust?", Some("Speaker 1")),
+            segment(2.0, 6.0, " A systems language.", Some("Speaker 2")),
+            segment(6.0, 8.0, " Without a GC.", Some("Speaker 2")),
+        ];
+
+        let input = TranscriptInput::new(&merge_passages(&segments, 20.0));
+        assert_eq!(
+            input.text,
+            "[00:00:00] Speaker 1: So what is Rust?\n\
+             [00:00:02] Speaker 2: A systems language. Without a GC.\n"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{llm::LlmError, provider::ModelPricing};
+
+/// Completion size assumed when checking the budget before a call is made
+pub const EXPECTED_COMPLETION_TOKENS: u64 = 4_000;
+
+/// One request/response round trip to a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmCall {
+    pub stage: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// `true` when the provider sent no `usage` and the counts are estimates
+    pub estimated: bool,
+    /// `true` when the reply came from the response cache and cost nothing
+    #[serde(default)]
+    pub cached: bool,
+    pub latency_ms: u64,
+    pub cost_usd: f64,
+}
+
+/// All LLM calls made on behalf of one job
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobUsage {
+    pub calls: Vec<LlmCall>,
+}
+
+impl JobUsage {
+    pub fn total_cost_usd(&self) -> f64 {
+        self.calls.iter().map(|c| c.cost_usd).sum()
+    }
+
+    pub fn total_prompt_tokens(&self) -> u64 {
+        self.calls.iter().map(|c| c.prompt_tokens).sum()
+    }
+
+    pub fn total_completion_tokens(&self) -> u64 {
+        self.calls.iter().map(|c| c.completion_tokens).sum()
+    }
+
+    pub fn total_latency_ms(&self) -> u64 {
+        self.calls.iter().map(|c| c.latency_ms).sum()
+    }
+}
+
+impl ModelPricing {
+    pub fn cost_usd(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.input_per_million
+            + completion_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Shared ledger for a job, enforcing the optional spending cap
+///
+/// Calls reserve their estimated cost up front, so parallel requests cannot
+/// jointly overshoot the budget while they are in flight.
+pub struct UsageTracker {
+    max_cost_usd: Option<f64>,
+    state: Mutex<TrackerState>,
+}
+
+struct TrackerState {
+    usage: JobUsage,
+    reserved_usd: f64,
+}
+
+impl UsageTracker {
+    /// Start from `usage` already spent by earlier stages of the same job
+    pub fn new(usage: JobUsage, max_cost_usd: Option<f64>) -> Self {
+        Self {
+            max_cost_usd,
+            state: Mutex::new(TrackerState {
+                usage,
+                reserved_usd: 0.0,
+            }),
+        }
+    }
+
+    /// Reserve `estimated_usd` for an upcoming call, failing if it would exceed the budget
+    pub fn reserve(&self, estimated_usd: f64) -> Result<(), LlmError> {
+        let mut state = self.state.lock().expect("UsageTracker poisoned");
+        let spent_usd = state.usage.total_cost_usd() + state.reserved_usd;
+
+        if let Some(max_cost_usd) = self.max_cost_usd
+            && spent_usd + estimated_usd > max_cost_usd
+        {
+            return Err(LlmError::BudgetExceeded {
+                spent_usd,
+                estimated_usd,
+                max_cost_usd,
+            });
+        }
+
+        state.reserved_usd += estimated_usd;
+        Ok(())
+    }
+
+    /// Release a reservation, recording the actual call if it completed
+    pub fn settle(&self, reserved_usd: f64, call: Option<LlmCall>) {
+        let mut state = self.state.lock().expect("UsageTracker poisoned");
+        state.reserved_usd = (state.reserved_usd - reserved_usd).max(0.0);
+        if let Some(call) = call {
+            state.usage.calls.push(call);
+        }
+    }
+
+    pub fn usage(&self) -> JobUsage {
+        self.state
+            .lock()
+            .expect("UsageTracker poisoned")
+            .usage
+            .clone()
+    }
+}
+
+/// Read `(prompt, completion)` token counts from a provider `usage` object
+pub fn parse_usage(response: &serde_json::Value) -> Option<(u64, u64)> {
+    let usage = &response["usage"];
+    // responses API uses input/output, chat completions uses prompt/completion
+    let prompt = usage["input_tokens"]
+        .as_u64()
+        .or_else(|| usage["prompt_tokens"].as_u64())?;
+    let completion = usage["output_tokens"]
+        .as_u64()
+        .or_else(|| usage["completion_tokens"].as_u64())?;
+    Some((prompt, completion))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        llm::{ChatMessage, LlmClient},
+        provider::Provider,
+        testing::{MockLlmServer, MockReply},
+    };
+
+    fn call(cost_usd: f64) -> LlmCall {
+        LlmCall {
+            stage: "test".to_string(),
+            provider: "Grok".to_string(),
+            model: "mock".to_string(),
+            prompt_tokens: 1_000,
+            completion_tokens: 100,
+            estimated: false,
+            cached: false,
+            latency_ms: 5,
+            cost_usd,
+        }
+    }
+
+    async fn send(client: &LlmClient) -> Result<String, LlmError> {
+        let reply = client
+            .send(&[ChatMessage::user("hello")], 0.0, false, None)
+            .await?;
+        Ok(reply.content)
+    }
+
+    #[test]
+    fn settling_replaces_the_reservation_with_the_real_cost() {
+        let tracker = UsageTracker::new(JobUsage::default(), Some(1.0));
+        tracker.reserve(0.6).unwrap();
+        assert!(matches!(
+            tracker.reserve(0.6),
+            Err(LlmError::BudgetExceeded { .. })
+        ));
+
+        tracker.settle(0.6, Some(call(0.1)));
+        assert_eq!(tracker.usage().total_cost_usd(), 0.1);
+        tracker.reserve(0.6).unwrap();
+    }
+
+    #[tokio::test]
+    async fn over_budget_calls_are_never_sent() {
+        let server = MockLlmServer::start().await;
+        server.script("", vec![MockReply::content("hi")]).await;
+        let tracker = Arc::new(UsageTracker::new(JobUsage::default(), Some(0.0)));
+        let client = LlmClient::new(Provider::Grok, "test", Arc::clone(&tracker))
+            .with_base_url(Some(server.base_url()))
+            .without_api_key();
+
+        let err = send(&client).await.unwrap_err();
+        assert!(matches!(err, LlmError::BudgetExceeded { .. }));
+        assert!(server.requests().await.is_empty());
+        assert!(tracker.usage().calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_usage_is_estimated() {
+        let server = MockLlmServer::start().await;
+        let body = serde_json::json!({
+            "output": [{
+                "type": "message",
+                "content": [{ "type": "output_text", "text": "a reply without usage" }]
+            }]
+        });
+        server.script("", vec![MockReply::Recorded(body)]).await;
+        let tracker = Arc::new(UsageTracker::new(JobUsage::default(), None));
+        let client = LlmClient::new(Provider::Grok, "test", Arc::clone(&tracker))
+            .with_base_url(Some(server.base_url()))
+            .without_api_key();
+
+        assert_eq!(send(&client).await.unwrap(), "a reply without usage");
+        let usage = tracker.usage();
+        assert!(usage.calls[0].estimated);
+        assert!(usage.calls[0].prompt_tokens > 0 && usage.calls[0].completion_tokens > 0);
+        assert!(usage.total_cost_usd() > 0.0);
+    }
+}
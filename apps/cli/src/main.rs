@@ -1,30 +1,50 @@
 use std::{
-    sync::Arc,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
 use anyhow::Result;
 use bratishka_core::events::BusConfig;
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
 use tokio::fs;
 use uuid::Uuid;
 
 use crate::{
-    pipeline::start_pipeline,
+    cache::get_root_cache_dir,
+    export::{TranscriptFormat, export_transcript, load_job_transcript},
+    format::{
+        format_batch_table, format_markdown_for_terminal, format_models_table, format_run_summary,
+        format_usage_table,
+    },
+    media::{MediaTools, TranscriptSource, parse_date},
+    models::{DEFAULT_MODEL, MODELS, ModelSpec, ModelStore, find_model, parse_model},
+    pipeline::{StageConcurrency, start_pipeline},
     provider::Provider,
-    workers::events::{JobSpec, YoutubeUrlRequested},
+    whisper::{DEFAULT_TEMPERATURE_INC, Device, parse_whisper_language},
+    workers::events::JobSpec,
 };
 
+mod batch;
 mod cache;
+mod chunking;
+mod diarize;
 mod error;
+mod export;
 mod format;
 mod inteligence;
+mod llm;
+mod media;
+mod models;
 mod pipeline;
 mod pipeline_old;
 mod provider;
+#[cfg(test)]
+mod testing;
+mod timestamp;
 mod types;
+mod whisper;
 mod workers;
 
 fn format_duration(d: Duration) -> String {
@@ -55,15 +75,161 @@ impl From<CliProvider> for Provider {
     }
 }
 
+/// CLI wrapper for TranscriptSource enum
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum CliTranscriptSource {
+    #[default]
+    Auto,
+    Subs,
+    Whisper,
+}
+
+impl From<CliTranscriptSource> for TranscriptSource {
+    fn from(cli: CliTranscriptSource) -> Self {
+        match cli {
+            CliTranscriptSource::Auto => TranscriptSource::Auto,
+            CliTranscriptSource::Subs => TranscriptSource::Subs,
+            CliTranscriptSource::Whisper => TranscriptSource::Whisper,
+        }
+    }
+}
+
+/// CLI wrapper for TranscriptFormat enum
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum CliTranscriptFormat {
+    #[default]
+    Srt,
+    Vtt,
+    Txt,
+    Tsv,
+    Json,
+}
+
+impl From<CliTranscriptFormat> for TranscriptFormat {
+    fn from(cli: CliTranscriptFormat) -> Self {
+        match cli {
+            CliTranscriptFormat::Srt => TranscriptFormat::Srt,
+            CliTranscriptFormat::Vtt => TranscriptFormat::Vtt,
+            CliTranscriptFormat::Txt => TranscriptFormat::Txt,
+            CliTranscriptFormat::Tsv => TranscriptFormat::Tsv,
+            CliTranscriptFormat::Json => TranscriptFormat::Json,
+        }
+    }
+}
+
+/// CLI wrapper for Device enum
+#[derive(Clone, Copy, ValueEnum)]
+enum CliDevice {
+    Cpu,
+    Gpu,
+}
+
+impl From<CliDevice> for Device {
+    fn from(cli: CliDevice) -> Self {
+        match cli {
+            CliDevice::Cpu => Device::Cpu,
+            CliDevice::Gpu => Device::Gpu,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "bratishka")]
 #[command(
     about = "Download YouTube videos, transcribe with Whisper, and generate AI-powered reports"
 )]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// Video URL
-    url: String,
+    #[command(subcommand)]
+    command: Option<Command>,
 
+    /// Video, playlist or channel URL, or path to a local media file (e.g. a recording or podcast)
+    #[arg(required = true)]
+    input: Option<String>,
+
+    /// Print the report in the terminal once it is ready
+    #[arg(long)]
+    print: bool,
+
+    #[command(flatten)]
+    job: JobArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Process many inputs through one pipeline, one URL or path per line
+    Batch(Box<BatchArgs>),
+    /// Manage the whisper models used for transcription
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
+    },
+    /// Write what earlier runs produced to files other tools can open
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Write a job's transcript as subtitles, plain text, TSV or JSON
+    Transcript {
+        /// URL or media file the job ran on, or a transcript JSON file
+        job: String,
+
+        /// Output format
+        #[arg(short, long, default_value = "srt")]
+        format: CliTranscriptFormat,
+
+        /// File to write, or "-" for stdout; transcript.<format> in the current directory by default
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// Show every known model and which ones are installed
+    List,
+    /// Download a model, resuming an interrupted download
+    Pull {
+        #[arg(value_parser = parse_model)]
+        name: String,
+    },
+    /// Delete a model and any partial download of it
+    Rm {
+        #[arg(value_parser = parse_model)]
+        name: String,
+    },
+    /// Check installed models against their published SHA-256
+    Verify {
+        /// Model to check; all installed models when omitted
+        #[arg(value_parser = parse_model)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// File listing the inputs, or "-" to read them from stdin
+    file: String,
+
+    /// Jobs downloading or waiting on the LLM at the same time
+    #[arg(short, long, default_value_t = 2)]
+    jobs: usize,
+
+    /// Jobs transcribing at the same time
+    #[arg(long, default_value_t = 1)]
+    transcribe_jobs: usize,
+
+    #[command(flatten)]
+    job: JobArgs,
+}
+
+/// Options shared by every job
+#[derive(Args, Clone)]
+struct JobArgs {
     /// Report language (e.g., "en", "ru", "uk"). Defaults to video's detected language.
     #[arg(short, long)]
     lang: Option<String>,
@@ -75,6 +241,86 @@ struct Cli {
     /// Force re-processing even if cached files exist
     #[arg(short, long)]
     force: bool,
+
+    /// Download the full video instead of only its audio
+    #[arg(long)]
+    keep_video: bool,
+
+    /// Whisper model to transcribe with (see `bratishka models list`)
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_MODEL, value_parser = parse_model)]
+    model: String,
+
+    /// Spoken language for whisper (e.g. "de"); detected from the audio by default
+    #[arg(long, value_name = "LANG", value_parser = parse_whisper_language)]
+    source_lang: Option<String>,
+
+    /// Have whisper translate the speech to English
+    #[arg(long)]
+    translate: bool,
+
+    /// Decode with beam search of this width instead of greedily (slower, more accurate)
+    #[arg(long, value_name = "N")]
+    beam_size: Option<u32>,
+
+    /// Temperature step for retrying segments whisper fails to decode; 0 disables retries
+    #[arg(long, value_name = "STEP", default_value_t = DEFAULT_TEMPERATURE_INC)]
+    temperature_inc: f32,
+
+    /// Text to prime whisper with, e.g. names and jargon it should spell right
+    #[arg(long, value_name = "TEXT")]
+    initial_prompt: Option<String>,
+
+    /// CPU threads for whisper
+    #[arg(long, value_name = "N")]
+    threads: Option<u32>,
+
+    /// Run whisper on the CPU or GPU; the GPU when there is one by default
+    #[arg(long, value_name = "DEVICE")]
+    device: Option<CliDevice>,
+
+    /// Feed whisper the whole audio, silences and music breaks included
+    #[arg(long)]
+    no_vad: bool,
+
+    /// Keep whisper's output as is, repeated lines and phantom phrases included
+    #[arg(long)]
+    no_filter: bool,
+
+    /// Tell speakers apart and attribute statements to them in the report
+    #[arg(long)]
+    diarize: bool,
+
+    /// Number of speakers, when known (implies --diarize)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    speakers: Option<u16>,
+
+    /// Use the video's own subtitles, whisper, or subtitles with whisper as a fallback
+    #[arg(long, value_name = "SOURCE", default_value = "auto")]
+    transcript_source: CliTranscriptSource,
+
+    /// Abort if the estimated LLM spend for the job would exceed this many USD
+    #[arg(long, value_name = "USD")]
+    max_cost: Option<f64>,
+
+    /// Always call the LLM, even if an identical request was answered before
+    #[arg(long)]
+    no_llm_cache: bool,
+
+    /// Base URL for the provider API (e.g. a local proxy or OpenAI-compatible server)
+    #[arg(long, value_name = "URL")]
+    api_base_url: Option<String>,
+
+    /// Process at most this many videos of a playlist or channel
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Only videos uploaded on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    since: Option<String>,
+
+    /// Only videos uploaded on or before this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<String>,
 }
 
 fn create_spinner(msg: &str) -> ProgressBar {
@@ -100,31 +346,85 @@ extern "C" fn whisper_log_callback(
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Cli::parse();
-    let job = JobSpec::from_cli(args).await?;
-    println!("{} Checking model...", style("✓").green().bold());
+    let cli = Cli::parse();
     unsafe {
         whisper_rs::set_log_callback(Some(whisper_log_callback), std::ptr::null_mut());
     }
 
+    match cli.command {
+        Some(Command::Batch(args)) => run_batch(*args).await,
+        Some(Command::Models { command }) => run_models(command).await,
+        Some(Command::Export { command }) => run_export(command).await,
+        None => run_single(&cli.input.expect("required by clap"), cli.job, cli.print).await,
+    }
+}
+
+async fn run_single(input: &str, args: JobArgs, print: bool) -> Result<()> {
+    let job = JobSpec::from_args(input, &args)?;
+
     println!("Starting pipeline...");
-    let pipeline = start_pipeline(BusConfig {
-        session_id: Uuid::new_v4(),
-        strict_routing: false,
-    })
+    let mut pipeline = start_pipeline(
+        BusConfig {
+            session_id: Uuid::new_v4(),
+            strict_routing: false,
+        },
+        MediaTools::default(),
+        StageConcurrency::default(),
+    )
     .await?;
     println!("Pipeline started");
 
     println!("Publishing job...");
-    pipeline
-        .bus
-        .publish(Arc::new(YoutubeUrlRequested::new(job)));
+    let job_id = job.job_id;
+    let is_batch = job.is_batch();
+    pipeline.bus.publish(job.into_request());
 
     println!("Waiting for pipeline to finish...");
 
-    match tokio::time::timeout(Duration::from_secs(30 * 60), pipeline.done_rx).await?? {
+    if is_batch {
+        let outcome = pipeline
+            .wait_for_batch(job_id, |result| match result {
+                Ok(done) => {
+                    println!("{} {}", style("✓").green().bold(), done.report.title);
+                    if print {
+                        print_report(&done.markdown_path);
+                    }
+                }
+                Err(failed) => eprintln!(
+                    "{} job failed at {}: {}",
+                    style("✗").red().bold(),
+                    failed.stage,
+                    failed.message
+                ),
+            })
+            .await
+            .ok_or_else(|| anyhow::anyhow!("pipeline stopped before the batch finished"))?;
+
+        return match outcome {
+            Ok(done) => {
+                println!("{}", format_batch_table(&done.index));
+                println!("batch index saved at {}", done.index_path.display());
+                Ok(())
+            }
+            Err(failed) => {
+                eprintln!("pipeline failed at {}: {}", failed.stage, failed.message);
+                let _ = pipeline.shutdown_tx.send(());
+                Err(anyhow::anyhow!("pipeline failed"))
+            }
+        };
+    }
+
+    let outcome = tokio::time::timeout(Duration::from_secs(30 * 60), pipeline.wait_for_job(job_id))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("pipeline stopped before the job finished"))?;
+
+    match outcome {
         Ok(done) => {
-            println!("report saved at {}", done.display());
+            if print {
+                print_report(&done.markdown_path);
+            }
+            println!("report saved at {}", done.markdown_path.display());
+            println!("{}", format_usage_table(&done.usage));
             Ok(())
         }
         Err(failed) => {
@@ -134,3 +434,129 @@ async fn main() -> Result<()> {
         }
     }
 }
+
+fn print_report(markdown_path: &Path) {
+    match std::fs::read_to_string(markdown_path) {
+        Ok(markdown) => println!("{}", format_markdown_for_terminal(&markdown)),
+        Err(e) => eprintln!("could not read {}: {}", markdown_path.display(), e),
+    }
+}
+
+async fn run_batch(args: BatchArgs) -> Result<()> {
+    let inputs = batch::read_inputs(&args.file).await?;
+    println!("{} {} inputs", style("✓").green().bold(), inputs.len());
+
+    let mut pipeline = start_pipeline(
+        BusConfig {
+            session_id: Uuid::new_v4(),
+            strict_routing: false,
+        },
+        MediaTools::default(),
+        StageConcurrency {
+            media: args.jobs,
+            transcribe: args.transcribe_jobs,
+            llm: args.jobs,
+        },
+    )
+    .await?;
+
+    let entries = batch::run_inputs(&mut pipeline, inputs, &args.job).await;
+    let _ = pipeline.shutdown_tx.send(());
+
+    println!("{}", format_run_summary(&entries));
+    let failed = entries.iter().filter(|e| e.is_failed()).count();
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{failed} of {} jobs failed", entries.len()));
+    }
+    Ok(())
+}
+
+async fn run_models(command: ModelsCommand) -> Result<()> {
+    let store = ModelStore::new(&get_root_cache_dir());
+    let spec = |name: &str| -> &'static ModelSpec { find_model(name).expect("checked by clap") };
+
+    match command {
+        ModelsCommand::List => {
+            println!("{}", format_models_table(MODELS, &store.installed()));
+        }
+        ModelsCommand::Pull { name } => {
+            let spec = spec(&name);
+            if store.is_installed(spec) {
+                println!(
+                    "{} {} is already installed",
+                    style("✓").green().bold(),
+                    name
+                );
+                return Ok(());
+            }
+            let path = models::pull_with_progress(&store, spec).await?;
+            println!(
+                "{} {} verified and saved at {}",
+                style("✓").green().bold(),
+                name,
+                path.display()
+            );
+        }
+        ModelsCommand::Rm { name } => {
+            if store.remove(spec(&name)).await? {
+                println!("{} removed {}", style("✓").green().bold(), name);
+            } else {
+                println!("{} is not installed", name);
+            }
+        }
+        ModelsCommand::Verify { name } => {
+            let specs: Vec<&'static ModelSpec> = match name {
+                Some(name) => vec![spec(&name)],
+                None => store
+                    .installed()
+                    .into_iter()
+                    .filter(|m| m.size_bytes > 0)
+                    .map(|m| m.spec)
+                    .collect(),
+            };
+            let mut failed = 0;
+            for spec in specs {
+                match store.verify(spec).await {
+                    Ok(()) => println!("{} {}", style("✓").green().bold(), spec.name),
+                    Err(e) => {
+                        failed += 1;
+                        eprintln!("{} {}: {}", style("✗").red().bold(), spec.name, e);
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{failed} models failed verification"));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_export(command: ExportCommand) -> Result<()> {
+    match command {
+        ExportCommand::Transcript {
+            job,
+            format,
+            output,
+        } => {
+            let format: TranscriptFormat = format.into();
+            let transcript = load_job_transcript(&job)?;
+            let content = export_transcript(&transcript, format)?;
+
+            let output = output
+                .unwrap_or_else(|| PathBuf::from(format!("transcript.{}", format.extension())));
+            if output.as_os_str() == "-" {
+                print!("{content}");
+                return Ok(());
+            }
+            fs::write(&output, content).await?;
+            println!(
+                "{} {} segments written to {}",
+                style("✓").green().bold(),
+                transcript.segments.len(),
+                output.display()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+/// Turns downloaded media into the 16 kHz mono WAV whisper expects
+#[async_trait]
+pub trait AudioExtractor: Send + Sync {
+    async fn extract_audio(&self, media_path: &Path, audio_path: &Path) -> anyhow::Result<()>;
+}
+
+/// Default extractor backed by the `ffmpeg` executable
+pub struct Ffmpeg;
+
+#[async_trait]
+impl AudioExtractor for Ffmpeg {
+    async fn extract_audio(&self, media_path: &Path, audio_path: &Path) -> anyhow::Result<()> {
+        let output = Command::new("ffmpeg")
+            .arg("-y")
+            .arg("-i")
+            .arg(media_path)
+            .arg("-ar")
+            .arg("16000")
+            .arg("-ac")
+            .arg("1")
+            .arg(audio_path)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("Failed to extract audio from video file"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::{
+    cache::find_subtitles_in_cache,
+    media::{Playlist, VideoMetadata, parse_flat_playlist},
+};
+
+/// Which streams a download should contain
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaKind {
+    /// Best audio stream only, enough for transcription
+    AudioOnly,
+    /// Full video with audio
+    Video,
+}
+
+/// Downloads the media behind a URL into a job's cache directory
+#[async_trait]
+pub trait MediaFetcher: Send + Sync {
+    /// Fetch `url` into `cache_dir`, returning the path of the written file
+    ///
+    /// Audio-only downloads must be named `audio.*` so the cache can tell
+    /// them apart from videos in the same container.
+    async fn fetch_media(
+        &self,
+        url: &str,
+        cache_dir: &Path,
+        kind: MediaKind,
+    ) -> anyhow::Result<PathBuf>;
+
+    /// List the videos of a playlist or channel without downloading them
+    async fn list_playlist(&self, url: &str) -> anyhow::Result<Playlist>;
+
+    /// Look up a video's title, chapters and the like without downloading it
+    async fn fetch_metadata(&self, url: &str) -> anyhow::Result<VideoMetadata>;
+
+    /// Fetch the `lang` subtitle track into `cache_dir` as `subs.<lang>.<ext>`
+    ///
+    /// Returns `None` when yt-dlp wrote no subtitle file.
+    async fn fetch_subtitles(
+        &self,
+        url: &str,
+        cache_dir: &Path,
+        lang: &str,
+    ) -> anyhow::Result<Option<PathBuf>>;
+}
+
+/// Default fetcher backed by the `yt-dlp` executable
+pub struct YtDlp;
+
+#[async_trait]
+impl MediaFetcher for YtDlp {
+    async fn fetch_media(
+        &self,
+        url: &str,
+        cache_dir: &Path,
+        kind: MediaKind,
+    ) -> anyhow::Result<PathBuf> {
+        let (format, output_template) = match kind {
+            MediaKind::AudioOnly => ("bestaudio", cache_dir.join("audio.%(ext)s")),
+            MediaKind::Video => ("best", cache_dir.join("video.%(ext)s")),
+        };
+        let output = Command::new("yt-dlp")
+            .arg(url)
+            .arg("--no-playlist")
+            .arg("--print")
+            .arg("after_move:filepath")
+            .arg("--extractor-args")
+            .arg("youtube:player_client=android,web")
+            .arg("-f")
+            .arg(format)
+            .arg("-o")
+            .arg(&output_template)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}", output.status));
+        }
+
+        let stdout_str = String::from_utf8_lossy(output.stdout.as_slice());
+        let filepath = stdout_str.trim();
+
+        Ok(filepath.into())
+    }
+
+    async fn list_playlist(&self, url: &str) -> anyhow::Result<Playlist> {
+        let output = Command::new("yt-dlp")
+            .arg(url)
+            .arg("--flat-playlist")
+            .arg("-J")
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "yt-dlp could not list {url}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        Ok(parse_flat_playlist(&json))
+    }
+
+    async fn fetch_metadata(&self, url: &str) -> anyhow::Result<VideoMetadata> {
+        let output = Command::new("yt-dlp")
+            .arg(url)
+            .arg("--no-playlist")
+            .arg("--skip-download")
+            .arg("-J")
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "yt-dlp could not inspect {url}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        Ok(VideoMetadata::from_info(&info))
+    }
+
+    async fn fetch_subtitles(
+        &self,
+        url: &str,
+        cache_dir: &Path,
+        lang: &str,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let output = Command::new("yt-dlp")
+            .arg(url)
+            .arg("--no-playlist")
+            .arg("--skip-download")
+            .arg("--write-subs")
+            .arg("--write-auto-subs")
+            .arg("--sub-format")
+            .arg("vtt/srv3")
+            .arg("--sub-langs")
+            .arg(lang)
+            .arg("-o")
+            .arg(cache_dir.join("subs.%(ext)s"))
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "yt-dlp could not fetch {lang} subtitles: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(find_subtitles_in_cache(cache_dir))
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{media::pick_subtitle_lang, timestamp::format_timestamp};
+
+/// Longest description passed to the LLM; channel links and sponsor blurbs
+/// usually follow the useful part
+const PROMPT_DESCRIPTION_CHARS: usize = 2_000;
+
+/// What yt-dlp knows about a video before anything is downloaded
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    /// `YYYYMMDD`, as yt-dlp reports it
+    pub upload_date: Option<String>,
+    pub duration_seconds: Option<f64>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Chapters set by the creator, in order
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    pub thumbnail_url: Option<String>,
+    /// Spoken language, when YouTube knows it
+    pub language: Option<String>,
+    /// Subtitle track to fetch instead of running whisper, see [`pick_subtitle_lang`]
+    pub subtitle_lang: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+    pub title: String,
+}
+
+impl VideoMetadata {
+    /// Read the metadata out of `yt-dlp -J` output
+    pub fn from_info(info: &serde_json::Value) -> Self {
+        let string = |key: &str| {
+            info[key]
+                .as_str()
+                .filter(|s| !s.trim().is_empty())
+                .map(str::to_string)
+        };
+
+        let chapters = info["chapters"]
+            .as_array()
+            .map(|chapters| {
+                chapters
+                    .iter()
+                    .filter_map(|c| {
+                        Some(Chapter {
+                            start_seconds: c["start_time"].as_f64()?,
+                            end_seconds: c["end_time"].as_f64()?,
+                            title: c["title"].as_str()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            title: string("title"),
+            uploader: string("uploader").or_else(|| string("channel")),
+            upload_date: string("upload_date"),
+            duration_seconds: info["duration"].as_f64(),
+            description: string("description"),
+            tags: info["tags"]
+                .as_array()
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(|t| t.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            chapters,
+            thumbnail_url: string("thumbnail"),
+            language: string("language"),
+            subtitle_lang: pick_subtitle_lang(info),
+        }
+    }
+
+    /// Chapters overlapping `start..end`, for prompts that only see part of the video
+    pub fn chapters_between(&self, start: f64, end: f64) -> Vec<&Chapter> {
+        self.chapters
+            .iter()
+            .filter(|c| c.end_seconds > start && c.start_seconds < end)
+            .collect()
+    }
+
+    /// Plain-text block describing the video, for LLM prompts
+    pub fn prompt_context(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(title) = &self.title {
+            lines.push(format!("Title: {title}"));
+        }
+        if let Some(uploader) = &self.uploader {
+            lines.push(format!("Channel: {uploader}"));
+        }
+        if let Some(date) = &self.upload_date {
+            lines.push(format!("Uploaded: {date}"));
+        }
+        if !self.tags.is_empty() {
+            lines.push(format!("Tags: {}", self.tags.join(", ")));
+        }
+        if let Some(description) = &self.description {
+            let mut description: String =
+                description.chars().take(PROMPT_DESCRIPTION_CHARS).collect();
+            if description.len() < self.description.as_ref().map_or(0, String::len) {
+                description.push_str(" [...]");
+            }
+            lines.push(format!("Description:\n{description}"));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Chapters as `[MM:SS–MM:SS] title` lines, with hours on long videos
+pub fn format_chapters(chapters: &[&Chapter]) -> String {
+    chapters
+        .iter()
+        .map(|c| {
+            format!(
+                "[{}–{}] {}",
+                format_timestamp(c.start_seconds),
+                format_timestamp(c.end_seconds),
+                c.title
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_yt_dlp_info() {
+        let info = serde_json::json!({
+            "title": "Rust Ownership in 30 Seconds",
+            "channel": "Rustacean Station",
+            "upload_date": "20240315",
+            "duration": 1805.0,
+            "description": "",
+            "tags": ["rust", "ownership"],
+            "chapters": [
+                {"start_time": 0.0, "end_time": 600.0, "title": "Intro"},
+                {"start_time": 600.0, "end_time": 1805.0, "title": "Borrowing"},
+                {"start_time": 1805.0, "title": "broken"}
+            ],
+            "thumbnail": "https://i.ytimg.com/vi/abc/maxresdefault.jpg",
+            "language": "en",
+            "automatic_captions": {"en-orig": [{"ext": "vtt"}]}
+        });
+
+        let metadata = VideoMetadata::from_info(&info);
+        assert_eq!(metadata.uploader.as_deref(), Some("Rustacean Station"));
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.chapters.len(), 2);
+        assert_eq!(metadata.subtitle_lang.as_deref(), Some("en-orig"));
+
+        let later = metadata.chapters_between(900.0, 1200.0);
+        assert_eq!(later.len(), 1);
+        assert_eq!(format_chapters(&later), "[10:00–30:05] Borrowing");
+    }
+}
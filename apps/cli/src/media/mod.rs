@@ -0,0 +1,29 @@
+pub mod extractor;
+pub mod fetcher;
+pub mod metadata;
+pub mod playlist;
+pub mod subtitles;
+
+pub use extractor::*;
+pub use fetcher::*;
+pub use metadata::*;
+pub use playlist::*;
+pub use subtitles::*;
+
+use std::sync::Arc;
+
+/// External tools the media stages run, swappable for tests or other backends
+#[derive(Clone)]
+pub struct MediaTools {
+    pub fetcher: Arc<dyn MediaFetcher>,
+    pub extractor: Arc<dyn AudioExtractor>,
+}
+
+impl Default for MediaTools {
+    fn default() -> Self {
+        Self {
+            fetcher: Arc::new(YtDlp),
+            extractor: Arc::new(Ffmpeg),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+
+/// Videos listed by a playlist or channel, without downloading any of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Playlist {
+    pub id: String,
+    pub title: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub id: String,
+    pub url: String,
+    pub title: Option<String>,
+    /// `YYYYMMDD`, when the listing exposes it
+    pub upload_date: Option<String>,
+}
+
+/// Which entries of a playlist become jobs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistFilter {
+    /// Keep at most this many entries, in playlist order
+    pub limit: Option<usize>,
+    /// Inclusive lower bound on the upload date, `YYYYMMDD`
+    pub since: Option<String>,
+    /// Inclusive upper bound on the upload date, `YYYYMMDD`
+    pub until: Option<String>,
+}
+
+impl PlaylistFilter {
+    /// Entries without a known upload date are kept; flat listings of plain
+    /// playlists usually don't carry one, so dropping them would empty the batch
+    pub fn apply(&self, entries: Vec<PlaylistEntry>) -> Vec<PlaylistEntry> {
+        entries
+            .into_iter()
+            .filter(|entry| {
+                let Some(date) = entry.upload_date.as_deref() else {
+                    return true;
+                };
+                self.since.as_deref().is_none_or(|since| date >= since)
+                    && self.until.as_deref().is_none_or(|until| date <= until)
+            })
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// The URL to expand when `url` points at a playlist or channel, `None` for single videos
+///
+/// Channel roots list their tabs rather than videos, so they are pointed at `/videos`.
+pub fn collection_url(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map(|(_, rest)| rest)?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if !host.ends_with("youtube.com") {
+        return None;
+    }
+
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let path = path.trim_end_matches('/');
+    let has_param = |name: &str| {
+        query
+            .split('&')
+            .any(|pair| pair.split('=').next() == Some(name))
+    };
+
+    if path == "playlist" && has_param("list") {
+        return Some(url.to_string());
+    }
+
+    let mut segments = path.split('/');
+    let first = segments.next().unwrap_or_default();
+    let is_channel = first.starts_with('@')
+        || (matches!(first, "channel" | "c" | "user") && segments.next().is_some());
+    if !is_channel {
+        return None;
+    }
+
+    let depth = if first.starts_with('@') { 1 } else { 2 };
+    if path.split('/').count() == depth {
+        Some(format!("https://{host}/{path}/videos"))
+    } else {
+        Some(url.to_string())
+    }
+}
+
+/// The video id of a YouTube video URL, for links that jump to a timestamp
+///
+/// Handles `watch?v=`, `youtu.be/`, `shorts/`, `live/` and `embed/` links.
+pub fn youtube_video_id(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map(|(_, rest)| rest)?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let path = path.trim_end_matches('/');
+
+    let id = if host == "youtu.be" || host.ends_with(".youtu.be") {
+        path
+    } else if host.ends_with("youtube.com") {
+        match path.split_once('/') {
+            Some(("shorts" | "live" | "embed", id)) => id,
+            None if path == "watch" => query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("v="))
+                .unwrap_or_default(),
+            _ => "",
+        }
+    } else {
+        ""
+    };
+    let is_id = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    is_id.then(|| id.to_string())
+}
+
+/// Parse the output of `yt-dlp --flat-playlist -J`
+///
+/// Nested playlists (e.g. channel tabs) are flattened in order.
+pub fn parse_flat_playlist(json: &serde_json::Value) -> Playlist {
+    let mut entries = Vec::new();
+    collect_entries(json, &mut entries);
+
+    Playlist {
+        id: json["id"].as_str().unwrap_or_default().to_string(),
+        title: json["title"].as_str().map(str::to_string),
+        entries,
+    }
+}
+
+fn collect_entries(json: &serde_json::Value, entries: &mut Vec<PlaylistEntry>) {
+    for entry in json["entries"].as_array().into_iter().flatten() {
+        if entry["entries"].is_array() {
+            collect_entries(entry, entries);
+            continue;
+        }
+        let Some(id) = entry["id"].as_str() else {
+            continue;
+        };
+
+        let url = entry["url"]
+            .as_str()
+            .filter(|url| url.starts_with("http"))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={id}"));
+        let upload_date = entry["upload_date"]
+            .as_str()
+            .map(str::to_string)
+            .or_else(|| {
+                entry["timestamp"]
+                    .as_i64()
+                    .or_else(|| entry["release_timestamp"].as_i64())
+                    .map(unix_to_yyyymmdd)
+            });
+
+        entries.push(PlaylistEntry {
+            id: id.to_string(),
+            url,
+            title: entry["title"].as_str().map(str::to_string),
+            upload_date,
+        });
+    }
+}
+
+/// Normalize `YYYYMMDD` or `YYYY-MM-DD` to `YYYYMMDD`
+pub fn parse_date(input: &str) -> Result<String, String> {
+    let digits: String = input.chars().filter(|c| *c != '-').collect();
+    let valid = digits.len() == 8
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && (1..=12).contains(&digits[4..6].parse::<u32>().unwrap_or(0))
+        && (1..=31).contains(&digits[6..8].parse::<u32>().unwrap_or(0));
+    if valid {
+        Ok(digits)
+    } else {
+        Err(format!("expected YYYY-MM-DD or YYYYMMDD, got {input}"))
+    }
+}
+
+/// Civil date of a unix timestamp (UTC), as `YYYYMMDD`
+fn unix_to_yyyymmdd(timestamp: i64) -> String {
+    // Howard Hinnant's days_from_civil inverse
+    let z = timestamp.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}{month:02}{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, upload_date: Option<&str>) -> PlaylistEntry {
+        PlaylistEntry {
+            id: id.to_string(),
+            url: format!("https://www.youtube.com/watch?v={id}"),
+            title: None,
+            upload_date: upload_date.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn detects_collections() {
+        assert_eq!(
+            collection_url("https://www.youtube.com/playlist?list=PL123").as_deref(),
+            Some("https://www.youtube.com/playlist?list=PL123")
+        );
+        assert_eq!(
+            collection_url("https://www.youtube.com/@rustconf").as_deref(),
+            Some("https://www.youtube.com/@rustconf/videos")
+        );
+        assert_eq!(
+            collection_url("https://www.youtube.com/channel/UC123/streams").as_deref(),
+            Some("https://www.youtube.com/channel/UC123/streams")
+        );
+        assert_eq!(
+            collection_url("https://www.youtube.com/watch?v=abc&list=PL123"),
+            None
+        );
+        assert_eq!(collection_url("https://youtu.be/abc"), None);
+    }
+
+    #[test]
+    fn finds_video_ids() {
+        let id = |url| youtube_video_id(url);
+        assert_eq!(
+            id("https://www.youtube.com/watch?list=PL1&v=dQw4w9WgXcQ").as_deref(),
+            Some("dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            id("https://youtu.be/dQw4w9WgXcQ?t=42").as_deref(),
+            Some("dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            id("https://youtube.com/shorts/a-b_c").as_deref(),
+            Some("a-b_c")
+        );
+        assert_eq!(id("https://www.youtube.com/@rustconf"), None);
+        assert_eq!(id("https://vimeo.com/12345"), None);
+    }
+
+    #[test]
+    fn parses_nested_flat_playlist() {
+        let json = serde_json::json!({
+            "id": "UC123",
+            "title": "RustConf",
+            "entries": [
+                { "_type": "playlist", "entries": [
+                    { "id": "a", "url": "https://www.youtube.com/watch?v=a", "title": "A", "upload_date": "20240101" }
+                ]},
+                { "id": "b", "url": "b", "timestamp": 1_717_200_000 }
+            ]
+        });
+
+        let playlist = parse_flat_playlist(&json);
+        assert_eq!(playlist.title.as_deref(), Some("RustConf"));
+        assert_eq!(playlist.entries.len(), 2);
+        assert_eq!(playlist.entries[1].url, "https://www.youtube.com/watch?v=b");
+        assert_eq!(playlist.entries[1].upload_date.as_deref(), Some("20240601"));
+    }
+
+    #[test]
+    fn filters_by_date_and_limit() {
+        let filter = PlaylistFilter {
+            limit: Some(2),
+            since: Some("20240201".to_string()),
+            until: Some("20240331".to_string()),
+        };
+        let kept = filter.apply(vec![
+            entry("jan", Some("20240115")),
+            entry("feb", Some("20240201")),
+            entry("unknown", None),
+            entry("mar", Some("20240310")),
+        ]);
+
+        let ids: Vec<_> = kept.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["feb", "unknown"]);
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("2024-03-09").unwrap(), "20240309");
+        assert_eq!(parse_date("20240309").unwrap(), "20240309");
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("yesterday").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use std::path::Path;
+
+use crate::{
+    timestamp::parse_timestamp,
+    types::{Segment, Transcript},
+};
+
+/// Where a job's transcript comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TranscriptSource {
+    /// Native subtitles when the video has them, whisper otherwise
+    #[default]
+    Auto,
+    /// Native subtitles only; the job fails without them
+    Subs,
+    /// Always transcribe with whisper
+    Whisper,
+}
+
+/// Pick the subtitle track matching the spoken language from `yt-dlp -J` output
+///
+/// Manual subtitles win over automatic captions. YouTube offers automatic
+/// captions machine-translated into every language, so only the original
+/// track (`<lang>-orig`, or the plain `<lang>` one) is considered.
+pub fn pick_subtitle_lang(info: &serde_json::Value) -> Option<String> {
+    let language = info["language"].as_str();
+    let has_track = |tracks: &serde_json::Value, lang: &str| {
+        tracks[lang]
+            .as_array()
+            .is_some_and(|formats| !formats.is_empty())
+    };
+
+    let manual = &info["subtitles"];
+    if let Some(lang) = language
+        && has_track(manual, lang)
+    {
+        return Some(lang.to_string());
+    }
+    // without a detected language, a single manual track is unambiguous
+    if let Some(tracks) = manual.as_object() {
+        let langs: Vec<_> = tracks.keys().filter(|k| *k != "live_chat").collect();
+        if language.is_none() && langs.len() == 1 {
+            return Some(langs[0].clone());
+        }
+    }
+
+    let auto = &info["automatic_captions"];
+    let language = language?;
+    [format!("{language}-orig"), language.to_string()]
+        .into_iter()
+        .find(|lang| has_track(auto, lang))
+}
+
+/// Language code of a subtitle file written as `subs.<lang>.<ext>`
+///
+/// Region and `-orig` suffixes are dropped to match whisper's codes.
+pub fn subtitle_language(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let lang = stem.rsplit_once('.')?.1;
+    Some(lang.split('-').next().unwrap_or(lang).to_string())
+}
+
+/// Read a `.vtt` or `.srv3` subtitle file into a transcript
+pub fn load_subtitles(path: &Path) -> anyhow::Result<Transcript> {
+    let content = std::fs::read_to_string(path)?;
+    let segments = match path.extension().and_then(|e| e.to_str()) {
+        Some("vtt") => parse_vtt(&content),
+        Some("srv3") => parse_srv3(&content),
+        _ => anyhow::bail!("unsupported subtitle format: {}", path.display()),
+    };
+    if segments.is_empty() {
+        anyhow::bail!("subtitle file has no text: {}", path.display());
+    }
+
+    Ok(Transcript {
+        text: segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        segments,
+        language: subtitle_language(path).unwrap_or_else(|| "Unknown".to_string()),
+        model: None,
+        device: None,
+        non_speech: Vec::new(),
+        filtered: Vec::new(),
+    })
+}
+
+/// Parse WebVTT cues into segments
+///
+/// YouTube's automatic captions roll: each cue repeats the line shown by the
+/// previous one and spells out word timings inline. Tags are stripped and
+/// repeated lines dropped so every word ends up in exactly one segment.
+pub fn parse_vtt(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut previous: Vec<String> = Vec::new();
+
+    let content = content.replace("\r\n", "\n");
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+        let Some((start, end)) = parse_vtt_timing(timing) else {
+            continue;
+        };
+
+        let text: Vec<String> = lines
+            .map(|l| decode_entities(&strip_tags(l)).trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let new: Vec<&str> = text
+            .iter()
+            .filter(|l| !previous.contains(l))
+            .map(String::as_str)
+            .collect();
+        if !new.is_empty() {
+            segments.push(Segment {
+                start,
+                end,
+                text: new.join(" "),
+                words: Vec::new(),
+                speaker: None,
+            });
+        }
+        previous = text;
+    }
+
+    segments
+}
+
+fn parse_vtt_timing(line: &str) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    // cue settings such as `align:start position:0%` follow the end time
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Parse YouTube's `srv3` timed text XML into segments
+///
+/// Each `<p t=".." d="..">` is one segment, with times in milliseconds.
+pub fn parse_srv3(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+
+    let mut rest = content;
+    while let Some(open) = rest.find("<p ") {
+        rest = &rest[open..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..tag_end];
+        let body_end = rest.find("</p>").unwrap_or(rest.len());
+        let body = rest.get(tag_end + 1..body_end).unwrap_or_default();
+        rest = &rest[body_end..];
+
+        let (Some(t), Some(d)) = (xml_attr(tag, "t"), xml_attr(tag, "d")) else {
+            continue;
+        };
+        let (Ok(t), Ok(d)) = (t.parse::<f64>(), d.parse::<f64>()) else {
+            continue;
+        };
+        let text = decode_entities(&strip_tags(body))
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        segments.push(Segment {
+            start: t / 1000.0,
+            end: (t + d) / 1000.0,
+            text,
+            words: Vec::new(),
+            speaker: None,
+        });
+    }
+
+    segments
+}
+
+fn xml_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {name}=\"");
+    let start = tag.find(&needle)? + needle.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+fn strip_tags(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTO_VTT: &str = "WEBVTT
+Kind: captions
+Language: en
+
+00:00:00.160 --> 00:00:02.470 align:start position:0%
+ \nso<00:00:00.480><c> today</c><00:00:00.800><c> we</c><00:00:01.040><c> talk</c>
+
+00:00:02.470 --> 00:00:02.480 align:start position:0%
+so today we talk
+
+
+00:00:02.480 --> 00:00:05.110 align:start position:0%
+so today we talk
+about<00:00:02.800><c> ownership</c><00:00:03.520><c> &amp;</c><00:00:03.840><c> borrowing</c>
+";
+
+    #[test]
+    fn parses_rolling_auto_captions() {
+        let segments = parse_vtt(AUTO_VTT);
+        let texts: Vec<_> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["so today we talk", "about ownership & borrowing"]
+        );
+        assert_eq!(segments[0].start, 0.16);
+        assert_eq!(segments[1].end, 5.11);
+    }
+
+    #[test]
+    fn parses_manual_vtt() {
+        let vtt =
+            "WEBVTT\n\n1\n01:02:03.500 --> 01:02:05.000\n<v Speaker>Hello there</v>\nsecond line\n";
+        let segments = parse_vtt(vtt);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].start, 3723.5);
+        assert_eq!(segments[0].text, "Hello there second line");
+    }
+
+    #[test]
+    fn parses_srv3() {
+        let srv3 = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+<body>
+<p t="160" d="2310" w="1"><s ac="0">so</s><s t="320" ac="0"> today</s></p>
+<p t="2470" d="10" w="1" a="1">
+</p>
+<p t="2480" d="2630">it&#39;s &quot;ownership&quot;</p>
+</body></timedtext>"#;
+        let segments = parse_srv3(srv3);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "so today");
+        assert_eq!(segments[0].end, 2.47);
+        assert_eq!(segments[1].text, "it's \"ownership\"");
+    }
+
+    #[test]
+    fn picks_original_language_track() {
+        let info = serde_json::json!({
+            "language": "en",
+            "subtitles": {"live_chat": [{"ext": "json"}]},
+            "automatic_captions": {
+                "de": [{"ext": "vtt"}],
+                "en-orig": [{"ext": "vtt"}],
+                "en": [{"ext": "vtt"}],
+            },
+        });
+        assert_eq!(pick_subtitle_lang(&info).as_deref(), Some("en-orig"));
+
+        let manual = serde_json::json!({
+            "language": "uk",
+            "subtitles": {"uk": [{"ext": "vtt"}]},
+            "automatic_captions": {"uk-orig": [{"ext": "vtt"}]},
+        });
+        assert_eq!(pick_subtitle_lang(&manual).as_deref(), Some("uk"));
+
+        let none = serde_json::json!({"language": "en", "automatic_captions": {"de": []}});
+        assert_eq!(pick_subtitle_lang(&none), None);
+    }
+
+    #[test]
+    fn reads_language_from_file_name() {
+        assert_eq!(
+            subtitle_language(Path::new("/c/subs.en-orig.vtt")).as_deref(),
+            Some("en")
+        );
+        assert_eq!(
+            subtitle_language(Path::new("subs.pt-BR.srv3")).as_deref(),
+            Some("pt")
+        );
+    }
+}
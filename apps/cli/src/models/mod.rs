@@ -0,0 +1,46 @@
+pub mod registry;
+pub mod store;
+
+pub use registry::*;
+pub use store::*;
+
+use std::path::{Path, PathBuf};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// Path of the model `name`, downloading it first if it isn't installed yet
+pub async fn ensure_model(root_cache_dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    let spec = find_model(name).ok_or_else(|| anyhow::anyhow!("unknown whisper model {name}"))?;
+    let store = ModelStore::new(root_cache_dir);
+    if store.is_installed(spec) {
+        return Ok(store.path(spec));
+    }
+    pull_with_progress(&store, spec).await
+}
+
+/// Download a model while showing a progress bar
+pub async fn pull_with_progress(
+    store: &ModelStore,
+    spec: &'static ModelSpec,
+) -> anyhow::Result<PathBuf> {
+    let pb = ProgressBar::new(spec.size_mb as u64 * 1024 * 1024);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{msg} [{bar:30.cyan}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    pb.set_message(format!("Downloading {}", spec.file_name()));
+
+    let result = store
+        .pull(spec, |written, total| {
+            if let Some(total) = total {
+                pb.set_length(total);
+            }
+            pb.set_position(written);
+        })
+        .await;
+    pb.finish_and_clear();
+
+    Ok(result?)
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+/// Model used when `--model` is not given
+pub const DEFAULT_MODEL: &str = "medium-q5_0";
+
+/// A whisper.cpp model published in ggml format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelSpec {
+    /// Name as whisper.cpp publishes it, e.g. `small.en-q5_1`
+    pub name: &'static str,
+    /// Approximate download size
+    pub size_mb: u32,
+    /// SHA-256 of the published file, checked instead of the one the host
+    /// advertises; unpinned models trust the host
+    pub sha256: Option<&'static str>,
+}
+
+impl ModelSpec {
+    pub fn file_name(&self) -> String {
+        format!("ggml-{}.bin", self.name)
+    }
+
+    /// `.en` models only transcribe English, but do it better than their multilingual twins
+    pub fn is_english_only(&self) -> bool {
+        self.name.contains(".en")
+    }
+}
+
+const fn model(name: &'static str, size_mb: u32, sha256: Option<&'static str>) -> ModelSpec {
+    ModelSpec {
+        name,
+        size_mb,
+        sha256,
+    }
+}
+
+/// Every model `bratishka models pull` knows about, smallest first
+pub const MODELS: &[ModelSpec] = &[
+    model("tiny", 75, None),
+    model("tiny.en", 75, None),
+    model("tiny-q5_1", 31, None),
+    model("tiny.en-q5_1", 31, None),
+    model("tiny-q8_0", 42, None),
+    model("base", 142, None),
+    model("base.en", 142, None),
+    model("base-q5_1", 57, None),
+    model("base.en-q5_1", 57, None),
+    model("base-q8_0", 78, None),
+    model("small", 466, None),
+    model("small.en", 466, None),
+    model("small-q5_1", 181, None),
+    model("small.en-q5_1", 181, None),
+    model("small-q8_0", 252, None),
+    model("medium", 1533, None),
+    model("medium.en", 1533, None),
+    model("medium-q5_0", 514, None),
+    model("medium.en-q5_0", 514, None),
+    model("medium-q8_0", 785, None),
+    model("large-v1", 2951, None),
+    model("large-v2", 2951, None),
+    model("large-v2-q5_0", 1080, None),
+    model("large-v2-q8_0", 1500, None),
+    model("large-v3", 2951, None),
+    model("large-v3-q5_0", 1080, None),
+    model("large-v3-turbo", 1624, None),
+    model("large-v3-turbo-q5_0", 547, None),
+    model("large-v3-turbo-q8_0", 834, None),
+];
+
+/// Look a model up by name or by its `ggml-*.bin` file name
+pub fn find_model(name: &str) -> Option<&'static ModelSpec> {
+    let name = name
+        .strip_prefix("ggml-")
+        .map(|n| n.strip_suffix(".bin").unwrap_or(n))
+        .unwrap_or(name);
+    MODELS.iter().find(|m| m.name == name)
+}
+
+/// The registry name of a model file, for recording which model made a transcript
+pub fn model_name_from_path(path: &Path) -> Option<&'static str> {
+    let file_name = path.file_name()?.to_str()?;
+    find_model(file_name).map(|m| m.name)
+}
+
+/// Clap value parser for `--model`
+pub fn parse_model(name: &str) -> Result<String, String> {
+    find_model(name).map(|m| m.name.to_string()).ok_or_else(|| {
+        format!("unknown model {name}; run `bratishka models list` to see the available ones")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_models_by_name_or_file() {
+        let spec = find_model("ggml-small.en-q5_1.bin").unwrap();
+        assert_eq!(spec.name, "small.en-q5_1");
+        assert!(spec.is_english_only());
+        assert_eq!(
+            find_model("large-v3").unwrap().file_name(),
+            "ggml-large-v3.bin"
+        );
+        assert!(find_model(DEFAULT_MODEL).is_some());
+        assert!(find_model("huge").is_none());
+        assert_eq!(
+            model_name_from_path(Path::new("/cache/models/ggml-medium-q5_0.bin")),
+            Some("medium-q5_0")
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use std::path::{Path, PathBuf};
+
+use reqwest::{StatusCode, header};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{
+    cache::{get_model_dir, sha256_file},
+    models::ModelSpec,
+};
+
+/// Where whisper.cpp publishes its ggml models
+pub const HUGGINGFACE_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Hugging Face sends the SHA-256 of LFS files in this header on the redirect
+/// to the CDN; models pinned in the registry only use it as a cross-check
+const LINKED_ETAG_HEADER: &str = "x-linked-etag";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Download of {url} failed with status {status}")]
+    DownloadFailed { url: String, status: u16 },
+
+    #[error("No SHA-256 published for {url}, refusing to install it unverified")]
+    MissingChecksum { url: String },
+
+    #[error("{file} is pinned to SHA-256 {pinned}, but the host now publishes {published}")]
+    PinnedChecksumChanged {
+        file: String,
+        pinned: &'static str,
+        published: String,
+    },
+
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Model {0} is not installed")]
+    NotInstalled(&'static str),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// A model file found in the model directory
+pub struct InstalledModel {
+    pub spec: &'static ModelSpec,
+    pub size_bytes: u64,
+    /// A checksum was recorded when it was downloaded or last verified
+    pub verified: bool,
+    /// An interrupted download is waiting to be resumed
+    pub partial: bool,
+}
+
+/// Downloads and keeps track of whisper models under `<cache>/models`
+///
+/// Downloads go to `<file>.part` and are only renamed into place once their
+/// SHA-256 matches, so a model file that exists is always complete. The
+/// checksum is kept next to it in `<file>.sha256`.
+pub struct ModelStore {
+    dir: PathBuf,
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl ModelStore {
+    pub fn new(root_cache_dir: &Path) -> Self {
+        Self {
+            dir: get_model_dir(root_cache_dir),
+            base_url: HUGGINGFACE_BASE_URL.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Download from another host serving the same file layout
+    #[cfg(test)]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn path(&self, spec: &ModelSpec) -> PathBuf {
+        self.dir.join(spec.file_name())
+    }
+
+    fn part_path(&self, spec: &ModelSpec) -> PathBuf {
+        self.dir.join(format!("{}.part", spec.file_name()))
+    }
+
+    fn checksum_path(&self, spec: &ModelSpec) -> PathBuf {
+        self.dir.join(format!("{}.sha256", spec.file_name()))
+    }
+
+    fn url(&self, spec: &ModelSpec) -> String {
+        format!("{}/{}", self.base_url, spec.file_name())
+    }
+
+    pub fn is_installed(&self, spec: &ModelSpec) -> bool {
+        self.path(spec).is_file()
+    }
+
+    /// Models present on disk, fully or partially downloaded
+    pub fn installed(&self) -> Vec<InstalledModel> {
+        crate::models::MODELS
+            .iter()
+            .filter_map(|spec| {
+                let partial = self.part_path(spec).is_file();
+                let size_bytes = std::fs::metadata(self.path(spec)).map(|m| m.len());
+                if size_bytes.is_err() && !partial {
+                    return None;
+                }
+                Some(InstalledModel {
+                    spec,
+                    size_bytes: size_bytes.unwrap_or(0),
+                    verified: self.checksum_path(spec).is_file(),
+                    partial,
+                })
+            })
+            .collect()
+    }
+
+    /// The SHA-256 the host publishes for a model
+    pub async fn published_sha256(&self, spec: &ModelSpec) -> Result<String, ModelError> {
+        let url = self.url(spec);
+        // the header is on the redirect itself, not on the CDN response behind it
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let response = client.head(&url).send().await?;
+        if response.status().is_client_error() || response.status().is_server_error() {
+            return Err(ModelError::DownloadFailed {
+                url,
+                status: response.status().as_u16(),
+            });
+        }
+
+        response
+            .headers()
+            .get(LINKED_ETAG_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim_matches('"').to_lowercase())
+            .filter(|v| v.len() == 64 && v.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or(ModelError::MissingChecksum { url })
+    }
+
+    /// The SHA-256 a download must match
+    ///
+    /// A pinned model is held to its pin, and the host's checksum only has to
+    /// agree when the host sends one. Unpinned models take the host's.
+    async fn expected_sha256(&self, spec: &ModelSpec) -> Result<String, ModelError> {
+        let published = self.published_sha256(spec).await;
+        let Some(pinned) = spec.sha256 else {
+            return published;
+        };
+        match published {
+            Ok(published) if published != pinned => Err(ModelError::PinnedChecksumChanged {
+                file: spec.file_name(),
+                pinned,
+                published,
+            }),
+            _ => Ok(pinned.to_string()),
+        }
+    }
+
+    /// Download a model, resuming an interrupted download if there is one
+    ///
+    /// `on_progress` gets the bytes on disk and the total size, when known.
+    pub async fn pull(
+        &self,
+        spec: &ModelSpec,
+        mut on_progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<PathBuf, ModelError> {
+        fs::create_dir_all(&self.dir).await?;
+        let expected = self.expected_sha256(spec).await?;
+        let part_path = self.part_path(spec);
+
+        // a resumed download that fails the check may have been appended to a
+        // different file; the second attempt starts from scratch
+        for attempt in 0..2 {
+            if attempt > 0 {
+                fs::remove_file(&part_path).await?;
+            }
+            let resumed = self.download(spec, &part_path, &mut on_progress).await?;
+
+            let actual = hash_file(&part_path).await?;
+            if actual == expected {
+                fs::rename(&part_path, self.path(spec)).await?;
+                fs::write(self.checksum_path(spec), &expected).await?;
+                return Ok(self.path(spec));
+            }
+            if !resumed {
+                fs::remove_file(&part_path).await?;
+                return Err(ModelError::ChecksumMismatch {
+                    file: spec.file_name(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+        unreachable!("the second attempt never resumes")
+    }
+
+    /// Fetch the model into `part_path`, returning whether an earlier part was reused
+    async fn download(
+        &self,
+        spec: &ModelSpec,
+        part_path: &Path,
+        on_progress: &mut impl FnMut(u64, Option<u64>),
+    ) -> Result<bool, ModelError> {
+        let url = self.url(spec);
+        let offset = fs::metadata(part_path).await.map(|m| m.len()).unwrap_or(0);
+
+        let mut request = self.http.get(&url);
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+        let mut response = request.send().await?;
+
+        let (mut file, mut written) = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let file = fs::OpenOptions::new().append(true).open(part_path).await?;
+                (file, offset)
+            }
+            // the part already holds the whole file
+            StatusCode::RANGE_NOT_SATISFIABLE => return Ok(true),
+            status if status.is_success() => (fs::File::create(part_path).await?, 0),
+            status => {
+                return Err(ModelError::DownloadFailed {
+                    url,
+                    status: status.as_u16(),
+                });
+            }
+        };
+        let resumed = written > 0;
+        let total = response.content_length().map(|len| len + written);
+
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+            on_progress(written, total);
+        }
+        file.flush().await?;
+
+        Ok(resumed)
+    }
+
+    /// Check an installed model against its pinned SHA-256, or the published
+    /// one for unpinned models
+    ///
+    /// Unpinned models fall back to the checksum recorded at download time
+    /// when the host can't be reached.
+    pub async fn verify(&self, spec: &'static ModelSpec) -> Result<(), ModelError> {
+        let path = self.path(spec);
+        if !path.is_file() {
+            return Err(ModelError::NotInstalled(spec.name));
+        }
+
+        let expected = match spec.sha256 {
+            Some(pinned) => Ok(pinned.to_string()),
+            None => self.published_sha256(spec).await,
+        };
+        let expected = match expected {
+            Ok(expected) => expected,
+            Err(e) => match fs::read_to_string(self.checksum_path(spec)).await {
+                Ok(recorded) => recorded.trim().to_string(),
+                Err(_) => return Err(e),
+            },
+        };
+        let actual = hash_file(&path).await?;
+        if actual != expected {
+            return Err(ModelError::ChecksumMismatch {
+                file: spec.file_name(),
+                expected,
+                actual,
+            });
+        }
+
+        fs::write(self.checksum_path(spec), &expected).await?;
+        Ok(())
+    }
+
+    /// Delete a model along with any partial download and recorded checksum
+    ///
+    /// Returns whether there was anything to delete.
+    pub async fn remove(&self, spec: &ModelSpec) -> Result<bool, ModelError> {
+        let mut removed = false;
+        for path in [
+            self.path(spec),
+            self.part_path(spec),
+            self.checksum_path(spec),
+        ] {
+            match fs::remove_file(&path).await {
+                Ok(()) => removed = true,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(removed)
+    }
+}
+
+async fn hash_file(path: &Path) -> Result<String, ModelError> {
+    let path = path.to_path_buf();
+    let hash = tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(std::io::Error::other)??;
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header, method, path},
+    };
+
+    use super::*;
+    use crate::models::find_model;
+
+    const MODEL_BYTES: &[u8] = b"ggml model weights";
+    const MODEL_SHA256: &str = "16fefd6af016f99d21df1c78d0d0edd7bd15eef2524a9ee82e170a760cfc4bf5";
+
+    static PINNED_TINY: ModelSpec = ModelSpec {
+        name: "tiny",
+        size_mb: 75,
+        sha256: Some(MODEL_SHA256),
+    };
+
+    async fn serve_model(server: &MockServer, sha256: &str) {
+        Mock::given(method("HEAD"))
+            .and(path("/ggml-tiny.bin"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header(LINKED_ETAG_HEADER, format!("\"{sha256}\"").as_str()),
+            )
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ggml-tiny.bin"))
+            .and(header("range", "bytes=5-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(&MODEL_BYTES[5..]))
+            .with_priority(1)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ggml-tiny.bin"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(MODEL_BYTES))
+            .mount(server)
+            .await;
+    }
+
+    fn sha256(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    #[tokio::test]
+    async fn resumes_partial_download() {
+        let server = MockServer::start().await;
+        serve_model(&server, &sha256(MODEL_BYTES)).await;
+        let root = tempfile::tempdir().unwrap();
+        let store = ModelStore::new(root.path()).with_base_url(&server.uri());
+        let spec = find_model("tiny").unwrap();
+
+        std::fs::create_dir_all(get_model_dir(root.path())).unwrap();
+        std::fs::write(store.part_path(spec), &MODEL_BYTES[..5]).unwrap();
+
+        let path = store.pull(spec, |_, _| {}).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), MODEL_BYTES);
+        assert!(!store.part_path(spec).exists());
+        assert!(store.installed()[0].verified);
+        store.verify(spec).await.unwrap();
+
+        let ranged = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .any(|r| r.headers.get("range").is_some());
+        assert!(ranged);
+    }
+
+    #[tokio::test]
+    async fn discards_download_with_wrong_checksum() {
+        let server = MockServer::start().await;
+        serve_model(&server, &sha256(b"something else")).await;
+        let root = tempfile::tempdir().unwrap();
+        let store = ModelStore::new(root.path()).with_base_url(&server.uri());
+        let spec = find_model("tiny").unwrap();
+
+        let err = store.pull(spec, |_, _| {}).await.unwrap_err();
+        assert!(matches!(err, ModelError::ChecksumMismatch { .. }));
+        assert!(!store.path(spec).exists());
+        assert!(!store.part_path(spec).exists());
+    }
+
+    #[tokio::test]
+    async fn refuses_when_the_host_disagrees_with_the_pin() {
+        let server = MockServer::start().await;
+        serve_model(&server, &sha256(b"something else")).await;
+        let root = tempfile::tempdir().unwrap();
+        let store = ModelStore::new(root.path()).with_base_url(&server.uri());
+
+        let err = store.pull(&PINNED_TINY, |_, _| {}).await.unwrap_err();
+        assert!(matches!(err, ModelError::PinnedChecksumChanged { .. }));
+        assert!(!store.part_path(&PINNED_TINY).exists());
+    }
+
+    #[tokio::test]
+    async fn verifies_pinned_models_offline() {
+        let root = tempfile::tempdir().unwrap();
+        // nothing listens here, so only the pin can be checked against
+        let store = ModelStore::new(root.path()).with_base_url("http://127.0.0.1:9");
+        std::fs::create_dir_all(get_model_dir(root.path())).unwrap();
+        std::fs::write(store.path(&PINNED_TINY), MODEL_BYTES).unwrap();
+        store.verify(&PINNED_TINY).await.unwrap();
+
+        std::fs::write(store.path(&PINNED_TINY), b"tampered").unwrap();
+        let err = store.verify(&PINNED_TINY).await.unwrap_err();
+        assert!(matches!(err, ModelError::ChecksumMismatch { .. }));
+    }
+}
@@ -1,38 +1,170 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use bratishka_core::{
     events::{BusConfig, EventBus, EventBusBuilder, bus_builder},
     workers::{PipelineFailed, Worker},
 };
-use tokio::sync::{broadcast, oneshot};
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
 
 use crate::{
-    types::VideoReport,
+    media::MediaTools,
+    whisper::WhisperPool,
     workers::{
-        analyze_sections::AnalyzeSectionsWorker, cli_completion_sink::CliCompletionSinkWorker,
-        compile_report::CompileReportWorker, download_video::DownloadVideoWorker,
-        extract_audio::ExtractAudioWorker, transcribe_audio::TranscribeAudioWorker,
+        STAGE_QUEUE_CAPACITY,
+        analyze_sections::AnalyzeSectionsWorker,
+        batch_index::{BatchIndexWorker, BatchJobs},
+        cli_completion_sink::{
+            CliCompletionSinkWorker, CompletedBatch, CompletedJob, PipelineOutcome,
+        },
+        compile_report::CompileReportWorker,
+        diarize_speakers::DiarizeSpeakersWorker,
+        download_video::DownloadVideoWorker,
+        expand_playlist::ExpandPlaylistWorker,
+        extract_audio::ExtractAudioWorker,
+        fetch_metadata::FetchMetadataWorker,
+        fetch_subtitles::FetchSubtitlesWorker,
+        transcribe_audio::TranscribeAudioWorker,
     },
 };
 
+/// How many jobs each stage works on at once
+#[derive(Clone, Copy, Debug)]
+pub struct StageConcurrency {
+    /// Downloads and audio extraction
+    pub media: usize,
+    /// Whisper is CPU/GPU bound, so more than one rarely helps
+    pub transcribe: usize,
+    /// Section analysis and report compilation
+    pub llm: usize,
+}
+
+impl StageConcurrency {
+    /// Jobs to keep in the pipeline at once: enough to keep every stage busy
+    ///
+    /// Batch inputs and the videos of playlists are limited separately, so each
+    /// gets half of a stage queue and together they never overflow one.
+    pub fn jobs_in_flight(&self) -> usize {
+        (2 * (self.media + self.transcribe + self.llm)).min(STAGE_QUEUE_CAPACITY / 2)
+    }
+}
+
+impl Default for StageConcurrency {
+    fn default() -> Self {
+        Self {
+            media: 1,
+            transcribe: 1,
+            llm: 1,
+        }
+    }
+}
+
 pub struct PipelineHandle {
     pub bus: Arc<EventBus>,
     pub shutdown_tx: broadcast::Sender<()>,
-    pub done_rx: oneshot::Receiver<Result<VideoReport, PipelineFailed>>,
+    pub outcomes_rx: mpsc::UnboundedReceiver<PipelineOutcome>,
+    pub concurrency: StageConcurrency,
 }
 
-pub async fn start_pipeline(bus_config: BusConfig) -> Result<PipelineHandle, anyhow::Error> {
+impl PipelineHandle {
+    /// Next job or batch outcome, `None` once the pipeline has shut down
+    pub async fn next_outcome(&mut self) -> Option<PipelineOutcome> {
+        self.outcomes_rx.recv().await
+    }
+
+    /// Wait for the report of `job_id`, or for the failure that stopped it
+    ///
+    /// Returns `None` if the pipeline shut down first.
+    pub async fn wait_for_job(
+        &mut self,
+        job_id: Uuid,
+    ) -> Option<Result<CompletedJob, PipelineFailed>> {
+        while let Some(outcome) = self.outcomes_rx.recv().await {
+            match outcome {
+                PipelineOutcome::Job(Ok(done)) if done.job_id == job_id => return Some(Ok(done)),
+                PipelineOutcome::Job(Err(failed)) if failed.correlation_id == Some(job_id) => {
+                    return Some(Err(failed));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Wait for the batch expanded from `job_id`, calling `on_job` as its jobs finish
+    ///
+    /// Failed batch jobs are recorded in the index; only a failure of the
+    /// expansion itself ends the wait with an error. Outcomes of jobs outside
+    /// the batch are skipped.
+    pub async fn wait_for_batch(
+        &mut self,
+        job_id: Uuid,
+        mut on_job: impl FnMut(&Result<CompletedJob, PipelineFailed>),
+    ) -> Option<Result<CompletedBatch, PipelineFailed>> {
+        let mut batch_jobs: Option<HashSet<Uuid>> = None;
+        // outcomes can overtake the plan, so they wait for it here
+        let mut early = Vec::new();
+        while let Some(outcome) = self.outcomes_rx.recv().await {
+            match outcome {
+                PipelineOutcome::Batch(done) if done.job_id == job_id => return Some(Ok(done)),
+                PipelineOutcome::Job(Err(failed)) if failed.correlation_id == Some(job_id) => {
+                    return Some(Err(failed));
+                }
+                PipelineOutcome::Planned {
+                    job_id: planned,
+                    job_ids,
+                } if planned == job_id => {
+                    let jobs: HashSet<Uuid> = job_ids.into_iter().collect();
+                    early
+                        .drain(..)
+                        .filter(|result| {
+                            outcome_job_id(result).is_some_and(|id| jobs.contains(&id))
+                        })
+                        .for_each(|result| on_job(&result));
+                    batch_jobs = Some(jobs);
+                }
+                PipelineOutcome::Job(result) => match &batch_jobs {
+                    Some(jobs) => {
+                        if outcome_job_id(&result).is_some_and(|id| jobs.contains(&id)) {
+                            on_job(&result);
+                        }
+                    }
+                    None => early.push(result),
+                },
+                PipelineOutcome::Planned { .. } | PipelineOutcome::Batch(_) => {}
+            }
+        }
+        None
+    }
+}
+
+fn outcome_job_id(result: &Result<CompletedJob, PipelineFailed>) -> Option<Uuid> {
+    match result {
+        Ok(done) => Some(done.job_id),
+        Err(failed) => failed.correlation_id,
+    }
+}
+
+pub async fn start_pipeline(
+    bus_config: BusConfig,
+    tools: MediaTools,
+    concurrency: StageConcurrency,
+) -> Result<PipelineHandle, anyhow::Error> {
     let (shutdown_tx, shutdown_rx) = broadcast::channel::<()>(1);
-    let (done_tx, done_rx) = oneshot::channel::<Result<VideoReport, PipelineFailed>>();
-    let done_tx = Some(done_tx);
+    let (outcomes_tx, outcomes_rx) = mpsc::unbounded_channel::<PipelineOutcome>();
 
     println!("Building event bus...");
     let builder = EventBusBuilder::new(bus_config)
+        .subscribe(ExpandPlaylistWorker::subscription())
+        .subscribe(FetchMetadataWorker::subscription())
+        .subscribe(FetchSubtitlesWorker::subscription())
         .subscribe(DownloadVideoWorker::subscription())
         .subscribe(ExtractAudioWorker::subscription())
         .subscribe(TranscribeAudioWorker::subscription())
+        .subscribe(DiarizeSpeakersWorker::subscription())
         .subscribe(AnalyzeSectionsWorker::subscription())
         .subscribe(CompileReportWorker::subscription())
+        .subscribe(BatchIndexWorker::subscription())
         .subscribe(CliCompletionSinkWorker::subscription());
 
     println!("Builder is ready");
@@ -51,40 +183,81 @@ pub async fn start_pipeline(bus_config: BusConfig) -> Result<PipelineHandle, any
     println!("Drain tasks are ready");
 
     println!("Creating workers...");
-    let download_worker = DownloadVideoWorker;
-    let extract_audio_worker = ExtractAudioWorker;
-    let transcribe_audio_worker = TranscribeAudioWorker;
+    let batch_jobs = BatchJobs::new(concurrency.jobs_in_flight());
+    let expand_playlist_worker =
+        ExpandPlaylistWorker::new(Arc::clone(&tools.fetcher), batch_jobs.clone());
+    let fetch_metadata_worker = FetchMetadataWorker::new(Arc::clone(&tools.fetcher));
+    let fetch_subtitles_worker = FetchSubtitlesWorker::new(Arc::clone(&tools.fetcher));
+    let download_worker = DownloadVideoWorker::new(tools.fetcher);
+    let extract_audio_worker = ExtractAudioWorker::new(tools.extractor);
+    let transcribe_audio_worker =
+        TranscribeAudioWorker::new(WhisperPool::new(concurrency.transcribe));
+    let diarize_speakers_worker = DiarizeSpeakersWorker;
     let analyze_sections_worker = AnalyzeSectionsWorker;
     let compile_report_worker = CompileReportWorker;
-    let cli_completion_sink_worker = CliCompletionSinkWorker::new(done_tx);
+    let batch_index_worker = BatchIndexWorker::new(batch_jobs);
+    let cli_completion_sink_worker = CliCompletionSinkWorker::new(outcomes_tx);
 
     println!("Workers are ready");
 
     println!("Starting workers...");
-    tokio::spawn(download_worker.run(
+    tokio::spawn(expand_playlist_worker.run(
+        wiring.take(ExpandPlaylistWorker::SUBSCRIBER_ID).unwrap(),
+        arc_bus.clone(),
+        shutdown_rx.resubscribe(),
+    ));
+    tokio::spawn(fetch_metadata_worker.run_concurrent(
+        wiring.take(FetchMetadataWorker::SUBSCRIBER_ID).unwrap(),
+        arc_bus.clone(),
+        shutdown_rx.resubscribe(),
+        concurrency.media,
+    ));
+    tokio::spawn(fetch_subtitles_worker.run_concurrent(
+        wiring.take(FetchSubtitlesWorker::SUBSCRIBER_ID).unwrap(),
+        arc_bus.clone(),
+        shutdown_rx.resubscribe(),
+        concurrency.media,
+    ));
+    tokio::spawn(download_worker.run_concurrent(
         wiring.take(DownloadVideoWorker::SUBSCRIBER_ID).unwrap(),
         arc_bus.clone(),
         shutdown_rx.resubscribe(),
+        concurrency.media,
     ));
-    tokio::spawn(extract_audio_worker.run(
+    tokio::spawn(extract_audio_worker.run_concurrent(
         wiring.take(ExtractAudioWorker::SUBSCRIBER_ID).unwrap(),
         arc_bus.clone(),
         shutdown_rx.resubscribe(),
+        concurrency.media,
     ));
-    tokio::spawn(transcribe_audio_worker.run(
+    tokio::spawn(transcribe_audio_worker.run_concurrent(
         wiring.take(TranscribeAudioWorker::SUBSCRIBER_ID).unwrap(),
         arc_bus.clone(),
         shutdown_rx.resubscribe(),
+        concurrency.transcribe,
     ));
-    tokio::spawn(analyze_sections_worker.run(
+    tokio::spawn(diarize_speakers_worker.run_concurrent(
+        wiring.take(DiarizeSpeakersWorker::SUBSCRIBER_ID).unwrap(),
+        arc_bus.clone(),
+        shutdown_rx.resubscribe(),
+        concurrency.transcribe,
+    ));
+    tokio::spawn(analyze_sections_worker.run_concurrent(
         wiring.take(AnalyzeSectionsWorker::SUBSCRIBER_ID).unwrap(),
         arc_bus.clone(),
         shutdown_rx.resubscribe(),
+        concurrency.llm,
     ));
-    tokio::spawn(compile_report_worker.run(
+    tokio::spawn(compile_report_worker.run_concurrent(
         wiring.take(CompileReportWorker::SUBSCRIBER_ID).unwrap(),
         arc_bus.clone(),
         shutdown_rx.resubscribe(),
+        concurrency.llm,
+    ));
+    tokio::spawn(batch_index_worker.run(
+        wiring.take(BatchIndexWorker::SUBSCRIBER_ID).unwrap(),
+        arc_bus.clone(),
+        shutdown_rx.resubscribe(),
     ));
     tokio::spawn(cli_completion_sink_worker.run(
         wiring.take(CliCompletionSinkWorker::SUBSCRIBER_ID).unwrap(),
@@ -96,6 +269,225 @@ pub async fn start_pipeline(bus_config: BusConfig) -> Result<PipelineHandle, any
     Ok(PipelineHandle {
         bus: arc_bus,
         shutdown_tx,
-        done_rx,
+        outcomes_rx,
+        concurrency,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        cache::get_cache_dir,
+        media::{Playlist, PlaylistEntry, PlaylistFilter},
+        provider::Provider,
+        testing::{
+            FakeMedia, FakeMediaFetcher, MockLlmServer, MockReply, REPORT_FIXTURE,
+            REPORT_REQUEST_NEEDLE, SECTIONS_FIXTURE, SECTIONS_REQUEST_NEEDLE, TRANSCRIPT_FIXTURE,
+            XAI_REPORT_RESPONSE_FIXTURE, seed_transcript, transcribed_job,
+        },
+        workers::events::{JobSpec, MediaSource},
+    };
+
+    async fn run_job(job: JobSpec, tools: MediaTools) -> Result<CompletedJob, PipelineFailed> {
+        let mut pipeline = start_pipeline(
+            BusConfig {
+                session_id: uuid::Uuid::new_v4(),
+                strict_routing: false,
+            },
+            tools,
+            StageConcurrency::default(),
+        )
+        .await
+        .unwrap();
+        let job_id = job.job_id;
+        pipeline.bus.publish(job.into_request());
+
+        let done = tokio::time::timeout(Duration::from_secs(10), pipeline.wait_for_job(job_id))
+            .await
+            .expect("pipeline timed out")
+            .expect("pipeline shut down");
+        let _ = pipeline.shutdown_tx.send(());
+        done
+    }
+
+    async fn scripted_server(report: MockReply) -> MockLlmServer {
+        let server = MockLlmServer::start().await;
+        server
+            .script(
+                SECTIONS_REQUEST_NEEDLE,
+                vec![MockReply::content(SECTIONS_FIXTURE)],
+            )
+            .await;
+        server.script(REPORT_REQUEST_NEEDLE, vec![report]).await;
+        server
+    }
+
+    #[tokio::test]
+    async fn grok_job_reaches_report_offline() {
+        let server = scripted_server(MockReply::recorded(XAI_REPORT_RESPONSE_FIXTURE)).await;
+        let media = FakeMedia::default();
+        let root = tempfile::tempdir().unwrap();
+
+        let job = transcribed_job(Provider::Grok, root.path(), &server.base_url());
+        let done = run_job(job, media.tools())
+            .await
+            .unwrap_or_else(|f| panic!("failed at {}: {}", f.stage, f.message));
+
+        assert_eq!(
+            done.report.title,
+            "Rust Ownership and Borrowing in 30 Seconds"
+        );
+        assert_eq!(done.report.sections.len(), 2);
+        assert!(done.report_path.exists());
+        assert_eq!(done.usage.calls.len(), 2);
+        // the recorded response carries real usage numbers
+        assert_eq!(done.usage.calls[1].prompt_tokens, 1843);
+
+        assert_eq!(media.fetcher.calls(), 1);
+        assert_eq!(media.extractor.calls(), 1);
+
+        let requests = server.requests().await;
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|(path, _)| path == "/v1/responses"));
+        assert_eq!(requests[0].1["model"], "grok-4-1-fast");
+    }
+
+    #[tokio::test]
+    async fn fetch_error_fails_the_job() {
+        let root = tempfile::tempdir().unwrap();
+        let media = FakeMedia {
+            fetcher: Arc::new(FakeMediaFetcher::failing("video unavailable")),
+            ..Default::default()
+        };
+
+        let job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        let failed = run_job(job, media.tools())
+            .await
+            .err()
+            .expect("job should fail");
+
+        assert_eq!(failed.stage, DownloadVideoWorker::SUBSCRIBER_ID);
+        assert!(failed.message.contains("video unavailable"));
+    }
+
+    const SUBTITLES_VTT: &str =
+        "WEBVTT\n\n00:00:01.000 --> 00:00:04.000\nsubtitles say ownership moves values\n";
+
+    #[tokio::test]
+    async fn subtitles_replace_download_and_whisper() {
+        let server = scripted_server(MockReply::content(REPORT_FIXTURE)).await;
+        let media = FakeMedia {
+            fetcher: Arc::new(FakeMediaFetcher::with_subtitles(SUBTITLES_VTT)),
+            ..Default::default()
+        };
+        let root = tempfile::tempdir().unwrap();
+
+        let job = transcribed_job(Provider::Grok, root.path(), &server.base_url());
+        run_job(job, media.tools())
+            .await
+            .unwrap_or_else(|f| panic!("failed at {}: {}", f.stage, f.message));
+
+        assert_eq!(media.fetcher.calls(), 0);
+        assert_eq!(media.extractor.calls(), 0);
+        let requests = server.requests().await;
+        assert!(
+            requests[0]
+                .1
+                .to_string()
+                .contains("subtitles say ownership moves values")
+        );
+    }
+
+    #[tokio::test]
+    async fn playlist_expands_into_indexed_batch() {
+        let server = scripted_server(MockReply::content(REPORT_FIXTURE)).await;
+        let root = tempfile::tempdir().unwrap();
+        let entry = |id: &str, upload_date: &str| PlaylistEntry {
+            id: id.to_string(),
+            url: format!("https://www.youtube.com/watch?v={id}"),
+            title: Some(format!("Talk {id}")),
+            upload_date: Some(upload_date.to_string()),
+        };
+        let media = FakeMedia {
+            fetcher: Arc::new(FakeMediaFetcher::with_playlist(Playlist {
+                id: "PLconf".to_string(),
+                title: Some("Conference".to_string()),
+                entries: vec![
+                    entry("jan", "20240115"),
+                    entry("feb", "20240210"),
+                    entry("mar", "20240301"),
+                    entry("apr", "20240401"),
+                ],
+            })),
+            ..Default::default()
+        };
+        let video_cache = |id: &str| {
+            get_cache_dir(
+                root.path(),
+                &format!("https://www.youtube.com/watch?v={id}"),
+            )
+        };
+        seed_transcript(&video_cache("feb"), TRANSCRIPT_FIXTURE);
+        // a corrupt transcript makes this one fail without taking the batch down
+        seed_transcript(&video_cache("mar"), "not a transcript");
+
+        let job = JobSpec {
+            source: MediaSource::Url("https://www.youtube.com/playlist?list=PLconf".to_string()),
+            playlist_filter: PlaylistFilter {
+                limit: Some(2),
+                since: Some("20240201".to_string()),
+                until: None,
+            },
+            ..transcribed_job(Provider::Grok, root.path(), &server.base_url())
+        };
+        let job_id = job.job_id;
+        let mut pipeline = start_pipeline(
+            BusConfig {
+                session_id: uuid::Uuid::new_v4(),
+                strict_routing: false,
+            },
+            media.tools(),
+            StageConcurrency::default(),
+        )
+        .await
+        .unwrap();
+        pipeline.bus.publish(job.into_request());
+        // a job outside the batch must not reach its callback
+        let other_root = tempfile::tempdir().unwrap();
+        let mut other = transcribed_job(Provider::Grok, other_root.path(), &server.base_url());
+        other.max_cost_usd = Some(0.0);
+        pipeline.bus.publish(other.into_request());
+
+        let mut finished = 0;
+        let done = tokio::time::timeout(
+            Duration::from_secs(10),
+            pipeline.wait_for_batch(job_id, |_| finished += 1),
+        )
+        .await
+        .expect("pipeline timed out")
+        .expect("pipeline shut down")
+        .unwrap_or_else(|f| panic!("failed at {}: {}", f.stage, f.message));
+        let _ = pipeline.shutdown_tx.send(());
+
+        assert_eq!(finished, 2);
+        assert!(done.index_path.exists());
+        assert_eq!(done.index.title.as_deref(), Some("Conference"));
+
+        let entries = &done.index.entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("Talk feb"));
+        assert!(entries[0].report_path.as_ref().unwrap().exists());
+        assert!(entries[1].report_path.is_none());
+        assert!(
+            entries[1]
+                .error
+                .as_deref()
+                .unwrap()
+                .starts_with(TranscribeAudioWorker::SUBSCRIBER_ID)
+        );
+        assert_eq!(done.index.completed(), 1);
+    }
+}
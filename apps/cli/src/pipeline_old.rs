@@ -4,47 +4,12 @@ use tokio::{fs, process::Command};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
 use crate::{
-    cache::get_model_dir,
     error::{BratishkaError, Result},
     inteligence::analyze_sections,
     provider::Provider,
     types::{Segment, Transcript, VideoReport},
 };
 
-pub const MODEL_NAME: &str = "ggml-medium-q5_0.bin";
-
-pub async fn ensure_model(cache_dir: &Path) -> Result<PathBuf> {
-    let download_url = format!(
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{}",
-        MODEL_NAME
-    );
-    let model_dir = get_model_dir(cache_dir);
-
-    if !model_dir.exists() {
-        fs::create_dir_all(&model_dir).await?;
-    }
-
-    let model_path = model_dir.join(MODEL_NAME);
-    if !model_path.exists() {
-        let output = Command::new("curl")
-            .arg("-L")
-            .arg(&download_url)
-            .arg("-o")
-            .arg(&model_path)
-            .output()
-            .await?;
-
-        if !output.status.success() {
-            return Err(BratishkaError::ModelDownloadFailed {
-                url: download_url.to_string(),
-                reason: String::from_utf8_lossy(&output.stderr).to_string(),
-            });
-        }
-    }
-
-    Ok(model_path)
-}
-
 /// Download a video from URL using yt-dlp
 pub async fn download_video(url: &str, cache_dir: &Path) -> Result<PathBuf> {
     let output_template = cache_dir.join("video.%(ext)s");
@@ -138,6 +103,8 @@ pub async fn transcribe_audio(
             start: segment.start_timestamp() as f64 / 100.0,
             end: segment.end_timestamp() as f64 / 100.0,
             text: seg_text.to_string(),
+            words: Vec::new(),
+            speaker: None,
         };
         segments.push(seg);
 
@@ -151,6 +118,10 @@ pub async fn transcribe_audio(
         language: language.unwrap_or("Unknown").to_string(),
         segments,
         text,
+        model: None,
+        device: None,
+        non_speech: Vec::new(),
+        filtered: Vec::new(),
     };
 
     fs::write(output_path, serde_json::to_string_pretty(&transcript)?).await?;
@@ -234,7 +205,7 @@ pub async fn generate_report(
     );
 
     let response = reqwest::Client::new()
-        .post(config.api_url)
+        .post(config.api_url(None))
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", api_key))
         .json(&serde_json::json!({
@@ -12,29 +12,80 @@ pub enum Provider {
     Gemini,
 }
 
+/// Wire format spoken by a provider's endpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiFormat {
+    /// `/v1/responses` (`input` in, `output[]` back)
+    Responses,
+    /// `/v1/chat/completions` (`messages` in, `choices[]` back)
+    ChatCompletions,
+}
+
+/// USD per million tokens, used to estimate what a job costs
+#[derive(Clone, Copy, Debug)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
 pub struct ProviderConfig {
-    pub api_url: &'static str,
+    /// Scheme, host and any fixed prefix; replaceable to point at a local stand-in
+    pub base_url: &'static str,
+    pub api_path: &'static str,
+    pub api_format: ApiFormat,
     pub model: &'static str,
     pub env_var: &'static str,
+    /// Whether the endpoint accepts a JSON Schema to constrain the response
+    pub supports_structured_output: bool,
+    pub pricing: ModelPricing,
+}
+
+impl ProviderConfig {
+    /// Full endpoint URL, optionally on another host (e.g. a local mock or proxy)
+    pub fn api_url(&self, base_url_override: Option<&str>) -> String {
+        let base_url = base_url_override.unwrap_or(self.base_url);
+        format!("{}{}", base_url.trim_end_matches('/'), self.api_path)
+    }
 }
 
 impl Provider {
     pub fn config(&self) -> ProviderConfig {
         match self {
             Provider::Grok => ProviderConfig {
-                api_url: "https://api.x.ai/v1/responses",
+                base_url: "https://api.x.ai",
+                api_path: "/v1/responses",
+                api_format: ApiFormat::Responses,
                 model: "grok-4-1-fast",
                 env_var: "XAI_API_KEY",
+                supports_structured_output: true,
+                pricing: ModelPricing {
+                    input_per_million: 0.20,
+                    output_per_million: 0.50,
+                },
             },
             Provider::Openai => ProviderConfig {
-                api_url: "https://api.openai.com/v1/chat/completions",
+                base_url: "https://api.openai.com",
+                api_path: "/v1/chat/completions",
+                api_format: ApiFormat::ChatCompletions,
                 model: "gpt-5.1",
                 env_var: "OPENAI_API_KEY",
+                supports_structured_output: true,
+                pricing: ModelPricing {
+                    input_per_million: 1.25,
+                    output_per_million: 10.0,
+                },
             },
             Provider::Gemini => ProviderConfig {
-                api_url: "https://generativelanguage.googleapis.com/v1beta/openai/chat/completions",
+                base_url: "https://generativelanguage.googleapis.com/v1beta/openai",
+                api_path: "/chat/completions",
+                api_format: ApiFormat::ChatCompletions,
                 model: "gemini-3-pro",
                 env_var: "GEMINI_API_KEY",
+                supports_structured_output: true,
+                pricing: ModelPricing {
+                    input_per_million: 2.0,
+                    output_per_million: 12.0,
+                },
             },
         }
     }
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use bratishka_core::{
+    events::{BusConfig, EnrichedEvent, Event, EventBus, EventBusBuilder},
+    queues::QueueKind,
+    workers::{FifoReceiver, InputSpec, SubscriptionSpec, Worker, WorkerInputs},
+};
+use uuid::Uuid;
+
+const CAPTURE_SUBSCRIBER_ID: &str = "test.capture";
+
+/// A bus that keeps what a worker publishes, for testing one stage without the
+/// rest of the pipeline
+pub struct CaptureBus {
+    bus: EventBus,
+    inputs: WorkerInputs,
+}
+
+impl CaptureBus {
+    /// Keep every event of `event_types` published from now on
+    pub fn new(event_types: &[&'static str]) -> Self {
+        let (bus, mut wiring, _tasks) = EventBusBuilder::new(BusConfig {
+            session_id: Uuid::new_v4(),
+            strict_routing: false,
+        })
+        .subscribe(SubscriptionSpec {
+            subscriber_id: CAPTURE_SUBSCRIBER_ID,
+            inputs: event_types
+                .iter()
+                .map(|&event_type| InputSpec {
+                    event_type,
+                    queue_kind: QueueKind::FifoDropOldest { capacity: 16 },
+                })
+                .collect(),
+        })
+        .build()
+        .expect("capture subscription is valid");
+        let inputs = wiring
+            .take(CAPTURE_SUBSCRIBER_ID)
+            .expect("capture subscription is wired");
+        Self { bus, inputs }
+    }
+
+    /// Hand `event` to `worker` the way the bus would
+    pub async fn deliver<W: Worker>(
+        &self,
+        worker: &mut W,
+        event: Arc<dyn Event>,
+    ) -> anyhow::Result<()> {
+        let event = Arc::new(EnrichedEvent {
+            event,
+            ingest_ns: 0,
+            session_id: self.bus.session_id(),
+            ingested_at: tokio::time::Instant::now(),
+        });
+        worker.handle(event, &self.bus).await
+    }
+
+    /// Events published since the last call, oldest first within each type
+    pub fn take(&mut self) -> Vec<Arc<dyn Event>> {
+        let mut published = Vec::new();
+        for fifo in &mut self.inputs.fifos {
+            loop {
+                let event = match &mut fifo.receiver {
+                    FifoReceiver::FifoDropOldest(receiver) => receiver.try_recv(),
+                    FifoReceiver::Isolated(receiver) => receiver.try_recv().ok(),
+                };
+                let Some(event) = event else { break };
+                published.push(Arc::clone(&event.event));
+            }
+        }
+        published
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+
+use crate::media::{AudioExtractor, MediaFetcher, MediaKind, MediaTools, Playlist, VideoMetadata};
+
+/// Sample rate of the WAV files whisper reads
+const SAMPLE_RATE: u32 = 16_000;
+
+/// Fetcher that writes a placeholder file instead of downloading
+#[derive(Default)]
+pub struct FakeMediaFetcher {
+    calls: AtomicUsize,
+    error: Option<String>,
+    playlist: Playlist,
+    subtitles: Option<String>,
+    metadata: VideoMetadata,
+}
+
+impl FakeMediaFetcher {
+    /// A fetcher whose every download fails with `message`
+    pub fn failing(message: &str) -> Self {
+        Self {
+            error: Some(message.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// A fetcher that lists `playlist` for any playlist or channel URL
+    pub fn with_playlist(playlist: Playlist) -> Self {
+        Self {
+            playlist,
+            ..Default::default()
+        }
+    }
+
+    /// A fetcher that finds `vtt` as the video's English subtitles
+    pub fn with_subtitles(vtt: &str) -> Self {
+        Self {
+            subtitles: Some(vtt.to_string()),
+            metadata: VideoMetadata {
+                subtitle_lang: Some("en".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// A fetcher that reports `metadata` for any video
+    pub fn with_metadata(metadata: VideoMetadata) -> Self {
+        Self {
+            metadata,
+            ..Default::default()
+        }
+    }
+
+    /// Number of media downloads, not counting playlist or subtitle lookups
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl MediaFetcher for FakeMediaFetcher {
+    async fn fetch_media(
+        &self,
+        _url: &str,
+        cache_dir: &Path,
+        kind: MediaKind,
+    ) -> anyhow::Result<PathBuf> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(error) = &self.error {
+            anyhow::bail!("{error}");
+        }
+
+        let path = match kind {
+            MediaKind::AudioOnly => cache_dir.join("audio.m4a"),
+            MediaKind::Video => cache_dir.join("video.mp4"),
+        };
+        tokio::fs::write(&path, b"fake media").await?;
+        Ok(path)
+    }
+
+    async fn list_playlist(&self, _url: &str) -> anyhow::Result<Playlist> {
+        if let Some(error) = &self.error {
+            anyhow::bail!("{error}");
+        }
+        Ok(self.playlist.clone())
+    }
+
+    async fn fetch_metadata(&self, _url: &str) -> anyhow::Result<VideoMetadata> {
+        if let Some(error) = &self.error {
+            anyhow::bail!("{error}");
+        }
+        Ok(self.metadata.clone())
+    }
+
+    async fn fetch_subtitles(
+        &self,
+        _url: &str,
+        cache_dir: &Path,
+        lang: &str,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let Some(vtt) = &self.subtitles else {
+            return Ok(None);
+        };
+        let path = cache_dir.join(format!("subs.{lang}.vtt"));
+        tokio::fs::write(&path, vtt).await?;
+        Ok(Some(path))
+    }
+}
+
+/// Extractor that writes a second of 16 kHz mono silence
+#[derive(Default)]
+pub struct FakeAudioExtractor {
+    calls: AtomicUsize,
+}
+
+impl FakeAudioExtractor {
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl AudioExtractor for FakeAudioExtractor {
+    async fn extract_audio(&self, _media_path: &Path, audio_path: &Path) -> anyhow::Result<()> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        write_silence(audio_path, 1.0)
+    }
+}
+
+/// Write `seconds` of silence as a WAV file whisper can read
+pub fn write_silence(path: &Path, seconds: f64) -> anyhow::Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for _ in 0..(seconds * SAMPLE_RATE as f64) as usize {
+        writer.write_sample(0i16)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Fakes for both media tools, kept around so tests can inspect them
+#[derive(Clone, Default)]
+pub struct FakeMedia {
+    pub fetcher: Arc<FakeMediaFetcher>,
+    pub extractor: Arc<FakeAudioExtractor>,
+}
+
+impl FakeMedia {
+    pub fn tools(&self) -> MediaTools {
+        MediaTools {
+            fetcher: self.fetcher.clone(),
+            extractor: self.extractor.clone(),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use wiremock::{
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+    matchers::{body_string_contains, method},
+};
+
+use crate::llm::estimate_tokens;
+
+/// Local stand-in for the provider APIs
+///
+/// Speaks both wire formats: requests to a path ending in `/responses` get an
+/// xAI responses envelope, everything else a chat-completions one. Point a job
+/// at it through `JobSpec::api_base_url`.
+pub struct MockLlmServer {
+    server: MockServer,
+}
+
+/// One scripted answer
+#[derive(Clone)]
+pub enum MockReply {
+    /// Model output text, wrapped in whichever envelope the request asks for
+    Content(String),
+    /// A full provider response body captured from a real API, sent verbatim
+    Recorded(serde_json::Value),
+    /// An HTTP error with the given status and body
+    Error(u16, String),
+}
+
+impl MockReply {
+    pub fn content(content: impl Into<String>) -> Self {
+        Self::Content(content.into())
+    }
+
+    pub fn recorded(body: &str) -> Self {
+        Self::Recorded(serde_json::from_str(body).expect("recorded fixture is not valid JSON"))
+    }
+}
+
+impl MockLlmServer {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Answer requests whose body contains `needle` with `replies`, in order
+    ///
+    /// The last reply is repeated once the script runs out.
+    pub async fn script(&self, needle: &str, replies: Vec<MockReply>) {
+        assert!(!replies.is_empty(), "a script needs at least one reply");
+
+        Mock::given(method("POST"))
+            .and(body_string_contains(needle))
+            .respond_with(ScriptedResponder {
+                replies: Arc::new(Mutex::new(replies.into())),
+            })
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Paths and JSON bodies of every request received so far
+    pub async fn requests(&self) -> Vec<(String, serde_json::Value)> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| {
+                let body = serde_json::from_slice(&r.body).unwrap_or_default();
+                (r.url.path().to_string(), body)
+            })
+            .collect()
+    }
+}
+
+struct ScriptedResponder {
+    replies: Arc<Mutex<VecDeque<MockReply>>>,
+}
+
+impl Respond for ScriptedResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let reply = {
+            let mut replies = self.replies.lock().unwrap();
+            if replies.len() > 1 {
+                replies.pop_front().unwrap()
+            } else {
+                replies[0].clone()
+            }
+        };
+
+        match reply {
+            MockReply::Content(content) => {
+                ResponseTemplate::new(200).set_body_json(envelope(request, &content))
+            }
+            MockReply::Recorded(body) => ResponseTemplate::new(200).set_body_json(body),
+            MockReply::Error(status, body) => ResponseTemplate::new(status).set_body_string(body),
+        }
+    }
+}
+
+fn envelope(request: &Request, content: &str) -> serde_json::Value {
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap_or_default();
+    let model = body["model"].as_str().unwrap_or("mock");
+    let prompt_tokens = estimate_tokens(&body.to_string());
+    let completion_tokens = estimate_tokens(content);
+
+    if request.url.path().ends_with("/responses") {
+        serde_json::json!({
+            "id": "resp_mock",
+            "object": "response",
+            "status": "completed",
+            "model": model,
+            "output": [{
+                "type": "message",
+                "role": "assistant",
+                "status": "completed",
+                "content": [{ "type": "output_text", "text": content, "annotations": [] }]
+            }],
+            "usage": {
+                "input_tokens": prompt_tokens,
+                "output_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens
+            }
+        })
+    } else {
+        serde_json::json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": "stop"
+            }],
+            "usage": {
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens
+            }
+        })
+    }
+}
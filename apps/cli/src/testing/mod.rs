@@ -0,0 +1,70 @@
+//! Offline harness for running the pipeline under `cargo test`
+
+pub mod bus;
+pub mod media;
+pub mod mock_llm;
+
+pub use bus::*;
+pub use media::*;
+pub use mock_llm::*;
+
+use std::path::Path;
+
+use crate::{
+    provider::Provider,
+    workers::events::{JobSpec, MediaSource},
+};
+
+pub const TRANSCRIPT_FIXTURE: &str = include_str!("fixtures/transcript.json");
+/// Model output for the section analysis stage
+pub const SECTIONS_FIXTURE: &str = include_str!("fixtures/sections.json");
+/// Model output for the report stage
+pub const REPORT_FIXTURE: &str = include_str!("fixtures/report.json");
+/// Raw `/v1/responses` body for the report stage, including reasoning and search items
+pub const XAI_REPORT_RESPONSE_FIXTURE: &str = include_str!("fixtures/xai_report_response.json");
+
+/// Text that only appears in the section analysis request
+pub const SECTIONS_REQUEST_NEEDLE: &str = "video content analyzer";
+/// Text that only appears in the report request
+pub const REPORT_REQUEST_NEEDLE: &str = "report compiler";
+
+/// A job whose transcript is already cached under `root_cache_dir`, so whisper
+/// never runs. Media comes from [`FakeMedia`] and the LLM from [`MockLlmServer`].
+pub fn transcribed_job(provider: Provider, root_cache_dir: &Path, api_base_url: &str) -> JobSpec {
+    let cache_dir = root_cache_dir.join("job");
+    seed_transcript(&cache_dir, TRANSCRIPT_FIXTURE);
+
+    JobSpec {
+        job_id: uuid::Uuid::new_v4(),
+        batch_id: None,
+        source: MediaSource::Url("https://www.youtube.com/watch?v=offline".to_string()),
+        force: false,
+        keep_video: false,
+        transcript_source: Default::default(),
+        whisper: Default::default(),
+        diarize: false,
+        speakers: None,
+        provider,
+        requested_report_lang: None,
+        max_cost_usd: None,
+        llm_cache: false,
+        api_base_url: Some(api_base_url.to_string()),
+        without_api_key: true,
+        playlist_filter: Default::default(),
+        metadata: None,
+        cache_hits: Vec::new(),
+        root_cache_dir: root_cache_dir.to_path_buf(),
+        cache_dir,
+        model: crate::models::DEFAULT_MODEL.to_string(),
+    }
+}
+
+/// Put `transcript` in the cache so the job skips whisper
+pub fn seed_transcript(cache_dir: &Path, transcript: &str) {
+    std::fs::create_dir_all(cache_dir).unwrap();
+    std::fs::write(
+        crate::cache::get_transcript_path(cache_dir, &Default::default()),
+        transcript,
+    )
+    .unwrap();
+}
@@ -0,0 +1,83 @@
+/// `MM:SS`, or `HH:MM:SS` from an hour on
+pub fn format_timestamp(seconds: f64) -> String {
+    if seconds >= 3600.0 {
+        return format_clock(seconds);
+    }
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// Always `HH:MM:SS`, for input where a fixed width is easier to read reliably
+pub fn format_clock(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        total / 60 % 60,
+        total % 60
+    )
+}
+
+/// `HH:MM:SS` followed by `separator` and milliseconds, as subtitle formats
+/// want them: `,` for SRT, `.` for WebVTT
+pub fn format_subtitle_timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Seconds from `HH:MM:SS`, `MM:SS` or `SS`, with an optional `.` or `,`
+/// fraction on the seconds
+///
+/// Minutes and seconds after the first field must be below 60.
+pub fn parse_timestamp(s: &str) -> Option<f64> {
+    let fields: Vec<&str> = s.trim().split(':').collect();
+    if fields.len() > 3 {
+        return None;
+    }
+
+    let mut seconds = 0.0;
+    for (i, field) in fields.iter().enumerate() {
+        let is_last = i == fields.len() - 1;
+        let value: f64 = if is_last {
+            field.replace(',', ".").parse().ok()?
+        } else {
+            field.parse::<u64>().ok()? as f64
+        };
+        if !(value >= 0.0 && (i == 0 || value < 60.0)) {
+            return None;
+        }
+        seconds = seconds * 60.0 + value;
+    }
+    Some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_hours_and_milliseconds() {
+        assert_eq!(format_timestamp(125.9), "02:05");
+        assert_eq!(format_timestamp(3900.0), "01:05:00");
+        assert_eq!(format_clock(125.9), "00:02:05");
+        assert_eq!(format_subtitle_timestamp(3661.0005, ','), "01:01:01,001");
+        assert_eq!(format_subtitle_timestamp(59.9996, '.'), "00:01:00.000");
+    }
+
+    #[test]
+    fn parses_what_it_formats() {
+        assert_eq!(parse_timestamp("01:05:00"), Some(3900.0));
+        assert_eq!(parse_timestamp("65:00"), Some(3900.0));
+        assert_eq!(parse_timestamp("00:01:02,500"), Some(62.5));
+        assert_eq!(parse_timestamp("12.25"), Some(12.25));
+        assert_eq!(parse_timestamp("1:75"), None);
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+        assert_eq!(parse_timestamp("ab:00"), None);
+    }
+}
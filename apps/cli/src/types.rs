@@ -1,12 +1,33 @@
 use serde::{Deserialize, Serialize};
 
-use crate::format::format_report_readable;
+use crate::{
+    format::format_report_markdown, llm::JsonSchema, media::VideoMetadata, whisper::Device,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
     pub text: String,
     pub segments: Vec<Segment>,
     pub language: String,
+    /// Whisper model that produced the transcript; `None` for subtitles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Device whisper ran on; `None` for subtitles and older transcripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<Device>,
+    /// Stretches without speech (intros, breaks, dead air) that whisper skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub non_speech: Vec<Span>,
+    /// What the hallucination filter removed, trimmed or flagged, for auditing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filtered: Vec<FilteredSegment>,
+}
+
+/// A time range in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: f64,
+    pub end: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +35,57 @@ pub struct Segment {
     pub start: f64,
     pub end: f64,
     pub text: String,
+    /// Per-word timing; empty for subtitles and transcripts cached before words were recorded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+    /// Who is talking ("Speaker 1"), when the transcript was diarized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+}
+
+/// A whisper segment the hallucination filter acted on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredSegment {
+    pub action: FilterAction,
+    pub reason: FilterReason,
+    /// As whisper produced it, before any trimming
+    pub segment: Segment,
+    /// Mean natural log of the token probabilities
+    pub avg_logprob: f32,
+    pub no_speech_probability: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    /// Left out of the transcript
+    Removed,
+    /// Kept with a repeated run of words cut down to one occurrence
+    Trimmed,
+    /// Kept, but worth a second look
+    Flagged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterReason {
+    /// Same text as the segment before, or a phrase looping within the segment
+    Repetition,
+    /// A phrase whisper is known to invent, like "Thanks for watching!"
+    KnownHallucination,
+    /// Whisper itself thinks there was no speech and isn't sure of the text
+    NoSpeech,
+    LowConfidence,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Word {
+    pub start: f64,
+    pub end: f64,
+    /// Includes the leading space whisper puts before most words
+    pub text: String,
+    /// Mean probability of the word's tokens, 0..1
+    pub probability: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,7 +108,53 @@ pub struct Section {
 }
 
 impl VideoReport {
-    pub fn display(&self) -> String {
-        format_report_readable(self)
+    /// See [`format_report_markdown`]
+    pub fn to_markdown(&self, metadata: Option<&VideoMetadata>, video_url: Option<&str>) -> String {
+        format_report_markdown(self, metadata, video_url)
+    }
+}
+
+impl JsonSchema for VideoReport {
+    const SCHEMA_NAME: &'static str = "video_report";
+
+    fn json_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "summary": { "type": "string" },
+                "duration_minutes": { "type": "number" },
+                "language": { "type": "string" },
+                "difficulty": {
+                    "type": "string",
+                    "enum": ["Easy to understand", "Moderate cognitive load", "Cognitively demanding"]
+                },
+                "key_takeaways": { "type": "array", "items": { "type": "string" } },
+                "sections": { "type": "array", "items": Section::json_schema() }
+            },
+            "required": [
+                "title", "summary", "duration_minutes", "language",
+                "difficulty", "key_takeaways", "sections"
+            ],
+            "additionalProperties": false
+        })
+    }
+}
+
+impl JsonSchema for Section {
+    const SCHEMA_NAME: &'static str = "report_section";
+
+    fn json_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "start_seconds": { "type": "number" },
+                "end_seconds": { "type": "number" },
+                "title": { "type": "string" },
+                "summary": { "type": "string" }
+            },
+            "required": ["start_seconds", "end_seconds", "title", "summary"],
+            "additionalProperties": false
+        })
     }
 }
@@ -0,0 +1,385 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{FilteredSegment, Segment, Span};
+
+/// Sample rate the extractor writes and whisper expects
+pub const SAMPLE_RATE: usize = 16_000;
+
+/// Audio decoded per `state.full` call; ~38 MB of samples
+const CHUNK_SECONDS: usize = 600;
+
+/// How far before the nominal end of a chunk to look for a pause to cut at
+const CUT_SEARCH_SECONDS: usize = 30;
+
+/// Audio repeated from the end of the previous chunk, so the decoder doesn't
+/// start cold on the first word
+const OVERLAP_SECONDS: usize = 2;
+
+/// Granularity of the search for a quiet cut point
+const FRAME_SAMPLES: usize = SAMPLE_RATE / 10;
+
+/// A window of audio to decode
+pub struct AudioChunk {
+    /// Sample the window starts at, overlap included
+    pub window_start: usize,
+    /// First sample this chunk's segments are kept from; earlier ones
+    /// belong to the previous chunk
+    pub start: usize,
+    /// Sample the next chunk starts at
+    pub end: usize,
+    pub samples: Vec<f32>,
+}
+
+impl AudioChunk {
+    /// Shift `segments` decoded from this window to file time, dropping the
+    /// ones in the overlap
+    pub fn place_segments(&self, segments: Vec<Segment>) -> Vec<Segment> {
+        segments
+            .into_iter()
+            .map(|segment| self.to_file_time(segment))
+            .filter(|segment| self.owns(segment))
+            .collect()
+    }
+
+    /// Shift a segment decoded from this window to file time
+    pub fn to_file_time(&self, mut segment: Segment) -> Segment {
+        let offset = self.window_start as f64 / SAMPLE_RATE as f64;
+        segment.start += offset;
+        segment.end += offset;
+        for word in &mut segment.words {
+            word.start += offset;
+            word.end += offset;
+        }
+        segment
+    }
+
+    /// Whether a segment in file time is this chunk's rather than a repeat
+    /// from the overlap
+    pub fn owns(&self, segment: &Segment) -> bool {
+        (segment.start + segment.end) / 2.0 >= self.start as f64 / SAMPLE_RATE as f64
+    }
+
+    /// Non-speech `ranges` of this window's samples as file-time spans,
+    /// clipped to the part the chunk owns
+    pub fn place_spans(&self, ranges: &[Range<usize>]) -> Vec<Span> {
+        ranges
+            .iter()
+            .map(|r| (r.start + self.window_start).max(self.start)..(r.end + self.window_start))
+            .filter(|r| r.start < r.end)
+            .map(|r| Span {
+                start: r.start as f64 / SAMPLE_RATE as f64,
+                end: r.end as f64 / SAMPLE_RATE as f64,
+            })
+            .collect()
+    }
+}
+
+/// Reads a 16 kHz mono WAV as consecutive chunks cut at pauses, holding at
+/// most one chunk in memory
+pub struct ChunkedAudio {
+    reader: hound::WavReader<BufReader<File>>,
+    /// Samples from `buffer_start` up to what has been read so far
+    buffer: Vec<f32>,
+    buffer_start: usize,
+    next_start: usize,
+    total: usize,
+}
+
+impl ChunkedAudio {
+    /// Open `path`, skipping everything before `start_sample`
+    pub fn open(path: &Path, start_sample: usize) -> Result<Self, hound::Error> {
+        let mut reader = hound::WavReader::open(path)?;
+        let total = reader.duration() as usize;
+        let buffer_start = start_sample.saturating_sub(OVERLAP_SECONDS * SAMPLE_RATE);
+        reader.seek(buffer_start.min(total) as u32)?;
+
+        Ok(Self {
+            reader,
+            buffer: Vec::new(),
+            buffer_start,
+            next_start: start_sample,
+            total,
+        })
+    }
+
+    /// Length of the audio in samples
+    pub fn total_samples(&self) -> usize {
+        self.total
+    }
+
+    pub fn next_chunk(&mut self) -> Result<Option<AudioChunk>, hound::Error> {
+        let start = self.next_start;
+        if start >= self.total {
+            return Ok(None);
+        }
+
+        let target = start + CHUNK_SECONDS * SAMPLE_RATE;
+        let search = CUT_SEARCH_SECONDS * SAMPLE_RATE;
+        // a tail shorter than the search region is folded into this chunk
+        let end = if target + search >= self.total {
+            self.fill_to(self.total)?;
+            self.total
+        } else {
+            self.fill_to(target)?;
+            self.buffer_start
+                + quietest_point(
+                    &self.buffer,
+                    target - search - self.buffer_start,
+                    target - self.buffer_start,
+                )
+        };
+
+        let window_start = start
+            .saturating_sub(OVERLAP_SECONDS * SAMPLE_RATE)
+            .max(self.buffer_start);
+        let samples =
+            self.buffer[window_start - self.buffer_start..end - self.buffer_start].to_vec();
+
+        // keep the next chunk's overlap and whatever was read past the cut
+        let keep_from = end.saturating_sub(OVERLAP_SECONDS * SAMPLE_RATE).max(start);
+        self.buffer.drain(..keep_from - self.buffer_start);
+        self.buffer_start = keep_from;
+        self.next_start = end;
+
+        Ok(Some(AudioChunk {
+            window_start,
+            start,
+            end,
+            samples,
+        }))
+    }
+
+    fn fill_to(&mut self, sample: usize) -> Result<(), hound::Error> {
+        let missing = (sample - self.buffer_start).saturating_sub(self.buffer.len());
+        for s in self.reader.samples::<i16>().take(missing) {
+            self.buffer.push(s? as f32 / i16::MAX as f32);
+        }
+        Ok(())
+    }
+}
+
+/// Middle of the quietest frame in `samples[from..to]`
+fn quietest_point(samples: &[f32], from: usize, to: usize) -> usize {
+    let to = to.min(samples.len());
+    if to.saturating_sub(from) < FRAME_SAMPLES {
+        return to;
+    }
+
+    (from..=to - FRAME_SAMPLES)
+        .step_by(FRAME_SAMPLES)
+        .map(|frame| {
+            let energy: f32 = samples[frame..frame + FRAME_SAMPLES]
+                .iter()
+                .map(|s| s * s)
+                .sum();
+            (frame, energy)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(frame, _)| frame + FRAME_SAMPLES / 2)
+        .unwrap_or(to)
+}
+
+/// Join spans that continue across a chunk boundary
+pub fn merge_spans(spans: impl IntoIterator<Item = Span>) -> Vec<Span> {
+    let mut merged: Vec<Span> = Vec::new();
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// A finished chunk, saved so an interrupted transcription can resume
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    /// Sample the next chunk starts at
+    pub end: usize,
+    /// `None` when the chunk had no speech to detect it from
+    pub language: Option<String>,
+    pub model: Option<String>,
+    /// Already in file time
+    pub segments: Vec<Segment>,
+    /// Stretches of this chunk that were not decoded, in file time
+    #[serde(default)]
+    pub non_speech: Vec<Span>,
+    /// What the hallucination filter did in this chunk, in file time
+    #[serde(default)]
+    pub filtered: Vec<FilteredSegment>,
+}
+
+/// Finished chunks of one transcription, as `chunk_NNNN.json` files
+pub struct ChunkStore {
+    dir: PathBuf,
+}
+
+impl ChunkStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Chunks saved by an earlier run with the same model, in order
+    ///
+    /// Anything left by another model is discarded. Reading stops at the
+    /// first chunk that can't be parsed, since it was likely cut short.
+    pub fn load(&self, model: Option<&str>) -> std::io::Result<Vec<ChunkRecord>> {
+        let mut records = Vec::new();
+        while let Ok(json) = std::fs::read_to_string(self.path(records.len())) {
+            match serde_json::from_str::<ChunkRecord>(&json) {
+                Ok(record) => records.push(record),
+                Err(_) => break,
+            }
+        }
+
+        if records.iter().any(|r| r.model.as_deref() != model) {
+            self.clear()?;
+            return Ok(Vec::new());
+        }
+        Ok(records)
+    }
+
+    pub fn save(&self, index: usize, record: &ChunkRecord) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let tmp = self.dir.join("chunk.tmp");
+        std::fs::write(&tmp, serde_json::to_string(record)?)?;
+        std::fs::rename(tmp, self.path(index))
+    }
+
+    pub fn clear(&self) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("chunk_{index:04}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tone with a silent stretch every `pause_every` seconds
+    fn write_wav(path: &Path, seconds: usize, pause_every: usize) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for i in 0..seconds * SAMPLE_RATE {
+            let second = i / SAMPLE_RATE;
+            let silent = second % pause_every == pause_every - 1;
+            let sample = if silent { 0 } else { (i % 64) as i16 * 200 };
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    fn segment(start: f64, end: f64) -> Segment {
+        Segment {
+            start,
+            end,
+            text: String::new(),
+            words: Vec::new(),
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn cuts_long_audio_at_pauses_and_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let wav = dir.path().join("audio.wav");
+        write_wav(&wav, 1500, 45);
+
+        let mut audio = ChunkedAudio::open(&wav, 0).unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = audio.next_chunk().unwrap() {
+            assert_eq!(chunk.samples.len(), chunk.end - chunk.window_start);
+            chunks.push((chunk.window_start, chunk.start, chunk.end));
+        }
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].1, 0);
+        assert_eq!(chunks[2].2, 1500 * SAMPLE_RATE);
+        for pair in chunks.windows(2) {
+            let cut = pair[0].2;
+            assert_eq!(pair[1].1, cut);
+            assert_eq!(pair[1].0, cut - OVERLAP_SECONDS * SAMPLE_RATE);
+            // inside the silent 44th second of some minute-and-a-half
+            assert_eq!((cut / SAMPLE_RATE) % 45, 44);
+        }
+
+        let mut resumed = ChunkedAudio::open(&wav, chunks[1].1).unwrap();
+        let chunk = resumed.next_chunk().unwrap().unwrap();
+        assert_eq!((chunk.window_start, chunk.start, chunk.end), chunks[1]);
+    }
+
+    #[test]
+    fn places_segments_in_file_time() {
+        let chunk = AudioChunk {
+            window_start: 98 * SAMPLE_RATE,
+            start: 100 * SAMPLE_RATE,
+            end: 200 * SAMPLE_RATE,
+            samples: Vec::new(),
+        };
+
+        let placed = chunk.place_segments(vec![segment(0.0, 1.5), segment(1.5, 4.0)]);
+        assert_eq!(placed.len(), 1);
+        assert_eq!((placed[0].start, placed[0].end), (99.5, 102.0));
+
+        let spans = chunk.place_spans(&[0..SAMPLE_RATE, 0..3 * SAMPLE_RATE]);
+        assert_eq!(
+            spans,
+            [Span {
+                start: 100.0,
+                end: 101.0
+            }]
+        );
+
+        let earlier = Span {
+            start: 95.0,
+            end: 100.0,
+        };
+        assert_eq!(
+            merge_spans([earlier, spans[0]]),
+            [Span {
+                start: 95.0,
+                end: 101.0
+            }]
+        );
+    }
+
+    #[test]
+    fn discards_chunks_from_another_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ChunkStore::new(dir.path().join("transcript.chunks"));
+        let record = |end| ChunkRecord {
+            end,
+            language: Some("en".to_string()),
+            model: Some("tiny".to_string()),
+            segments: vec![segment(0.0, 1.0)],
+            non_speech: Vec::new(),
+            filtered: Vec::new(),
+        };
+        store.save(0, &record(10)).unwrap();
+        store.save(1, &record(20)).unwrap();
+
+        let loaded = store.load(Some("tiny")).unwrap();
+        assert_eq!(loaded.last().map(|r| r.end), Some(20));
+
+        assert!(store.load(Some("base")).unwrap().is_empty());
+        assert!(!dir.path().join("transcript.chunks").exists());
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fmt, process::Command, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+/// Where whisper runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    Cpu,
+    Gpu,
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cpu => write!(f, "CPU"),
+            Self::Gpu => write!(f, "GPU"),
+        }
+    }
+}
+
+/// Whether this build can use the GPU and the machine has one
+///
+/// whisper.cpp quietly decodes on the CPU when CUDA finds no device, so the
+/// driver is asked up front to know which device actually runs.
+pub fn gpu_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        cfg!(feature = "cuda")
+            && Command::new("nvidia-smi")
+                .arg("-L")
+                .output()
+                .is_ok_and(|out| out.status.success() && !out.stdout.is_empty())
+    })
+}
+
+/// The device to run on: `requested` if it can be used, otherwise the GPU
+/// when there is one
+pub fn resolve_device(requested: Option<Device>) -> anyhow::Result<Device> {
+    match requested {
+        Some(Device::Cpu) => Ok(Device::Cpu),
+        Some(Device::Gpu) if !cfg!(feature = "cuda") => {
+            anyhow::bail!("this build has no GPU support; reinstall with `--features cuda`")
+        }
+        Some(Device::Gpu) if !gpu_available() => {
+            anyhow::bail!("no CUDA GPU found (`nvidia-smi -L` lists none)")
+        }
+        Some(Device::Gpu) => Ok(Device::Gpu),
+        None if gpu_available() => Ok(Device::Gpu),
+        None => Ok(Device::Cpu),
+    }
+}
@@ -0,0 +1,245 @@
+use crate::types::{FilterAction, FilterReason, FilteredSegment, Segment};
+
+/// Whisper's own thresholds for treating a segment as decoded silence
+const NO_SPEECH_PROBABILITY: f32 = 0.6;
+const LOW_AVG_LOGPROB: f32 = -1.0;
+
+/// Longest phrase, in words, looked for when detecting loops
+const MAX_LOOP_WORDS: usize = 8;
+
+/// Phrases whisper invents over silence and music, learned from subtitle
+/// credits in its training data; matched against whole segments after
+/// normalizing case and punctuation
+const KNOWN_HALLUCINATIONS: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thank you so much for watching",
+    "please subscribe",
+    "like and subscribe",
+    "dont forget to like and subscribe",
+    "subscribe to my channel",
+    "see you in the next video",
+    "subtitles by the amaraorg community",
+    "transcription by castingwords",
+    "продолжение следует",
+    "спасибо за просмотр",
+    "субтитры сделал dimatorzok",
+    "субтитры создавал dimatorzok",
+    "untertitel im auftrag des zdf 2017",
+    "untertitel der amaraorg community",
+    "soustitres réalisés par la communauté damaraorg",
+    "ご視聴ありがとうございました",
+];
+
+/// A decoded segment with the scores the filter needs
+pub struct ScoredSegment {
+    pub segment: Segment,
+    /// Mean natural log of the token probabilities
+    pub avg_logprob: f32,
+    pub no_speech_probability: f32,
+}
+
+/// Drop and trim what whisper made up, returning the segments to keep and a
+/// record of everything acted on
+pub fn filter_segments(scored: Vec<ScoredSegment>) -> (Vec<Segment>, Vec<FilteredSegment>) {
+    let mut kept: Vec<Segment> = Vec::new();
+    let mut filtered = Vec::new();
+
+    for ScoredSegment {
+        segment,
+        avg_logprob,
+        no_speech_probability,
+    } in scored
+    {
+        let mut record = |action, reason, segment: &Segment| {
+            filtered.push(FilteredSegment {
+                action,
+                reason,
+                segment: segment.clone(),
+                avg_logprob,
+                no_speech_probability,
+            })
+        };
+
+        let text = normalize(&segment.text);
+        let removal = if text.is_empty()
+            || is_annotation(&segment.text)
+            || KNOWN_HALLUCINATIONS.contains(&text.as_str())
+        {
+            Some(FilterReason::KnownHallucination)
+        } else if no_speech_probability > NO_SPEECH_PROBABILITY && avg_logprob < LOW_AVG_LOGPROB {
+            Some(FilterReason::NoSpeech)
+        } else if kept
+            .last()
+            .is_some_and(|last| normalize(&last.text) == text)
+        {
+            Some(FilterReason::Repetition)
+        } else {
+            None
+        };
+        if let Some(reason) = removal {
+            record(FilterAction::Removed, reason, &segment);
+            continue;
+        }
+
+        let segment = match collapse_loop(&segment) {
+            Some(trimmed) => {
+                record(FilterAction::Trimmed, FilterReason::Repetition, &segment);
+                trimmed
+            }
+            None => segment,
+        };
+        if avg_logprob < LOW_AVG_LOGPROB {
+            record(FilterAction::Flagged, FilterReason::LowConfidence, &segment);
+        }
+        kept.push(segment);
+    }
+
+    (kept, filtered)
+}
+
+/// Lowercase letters and digits with single spaces between words
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whole-segment sound tags like "[Music]" or "(applause)"
+fn is_annotation(text: &str) -> bool {
+    let text = text.trim();
+    (text.starts_with('[') && text.ends_with(']'))
+        || (text.starts_with('(') && text.ends_with(')'))
+        || text.chars().all(|c| c == '♪' || c.is_whitespace())
+}
+
+/// The segment with the longest looping run of words cut to one occurrence,
+/// or `None` when nothing loops
+///
+/// A single word must repeat four times in a row to count, a longer phrase
+/// three times, so ordinary emphasis ("very, very") is left alone.
+fn collapse_loop(segment: &Segment) -> Option<Segment> {
+    let words: Vec<&str> = segment.text.split_whitespace().collect();
+    let normalized: Vec<String> = words.iter().map(|w| normalize(w)).collect();
+
+    // (start, phrase length, repeats) of the loop that removes the most words
+    let mut best: Option<(usize, usize, usize)> = None;
+    for n in 1..=MAX_LOOP_WORDS {
+        let min_repeats = if n == 1 { 4 } else { 3 };
+        for start in 0..normalized.len().saturating_sub(n * min_repeats - 1) {
+            let phrase = &normalized[start..start + n];
+            let repeats = 1
+                + (1..)
+                    .take_while(|r| {
+                        normalized
+                            .get(start + r * n..start + (r + 1) * n)
+                            .is_some_and(|next| next == phrase)
+                    })
+                    .count();
+            let removed = n * (repeats - 1);
+            if repeats >= min_repeats && best.is_none_or(|(_, bn, br)| removed > bn * (br - 1)) {
+                best = Some((start, n, repeats));
+            }
+        }
+    }
+    let (start, n, repeats) = best?;
+    let cut = start + n..start + n * repeats;
+
+    let mut kept_words: Vec<&str> = words.clone();
+    kept_words.drain(cut.clone());
+    let leading_space = if segment.text.starts_with(' ') {
+        " "
+    } else {
+        ""
+    };
+    let mut trimmed = segment.clone();
+    trimmed.text = format!("{leading_space}{}", kept_words.join(" "));
+    // word timing only lines up when whisper's words are the text's words
+    if trimmed.words.len() == words.len() {
+        trimmed.words.drain(cut);
+    } else {
+        trimmed.words.clear();
+    }
+    if let Some(last) = trimmed.words.last() {
+        trimmed.end = last.end;
+    }
+    Some(trimmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scored(text: &str, avg_logprob: f32, no_speech_probability: f32) -> ScoredSegment {
+        ScoredSegment {
+            segment: Segment {
+                start: 0.0,
+                end: 1.0,
+                text: text.to_string(),
+                words: Vec::new(),
+                speaker: None,
+            },
+            avg_logprob,
+            no_speech_probability,
+        }
+    }
+
+    fn actions(filtered: &[FilteredSegment]) -> Vec<(FilterAction, FilterReason)> {
+        filtered.iter().map(|f| (f.action, f.reason)).collect()
+    }
+
+    #[test]
+    fn removes_phantom_and_repeated_segments() {
+        let (kept, filtered) = filter_segments(vec![
+            scored(" Ownership moves values.", -0.2, 0.01),
+            scored(" Ownership moves values.", -0.3, 0.02),
+            scored(" Thanks for watching!", -0.4, 0.3),
+            scored(" [Music]", -0.5, 0.4),
+            scored(" I'll see you", -1.4, 0.8),
+            scored(" Borrowing lends them.", -1.2, 0.1),
+        ]);
+
+        let texts: Vec<&str> = kept.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [" Ownership moves values.", " Borrowing lends them."]
+        );
+        assert_eq!(
+            actions(&filtered),
+            [
+                (FilterAction::Removed, FilterReason::Repetition),
+                (FilterAction::Removed, FilterReason::KnownHallucination),
+                (FilterAction::Removed, FilterReason::KnownHallucination),
+                (FilterAction::Removed, FilterReason::NoSpeech),
+                (FilterAction::Flagged, FilterReason::LowConfidence),
+            ]
+        );
+    }
+
+    #[test]
+    fn collapses_looping_phrases() {
+        let (kept, filtered) = filter_segments(vec![
+            scored(
+                " So we move it. I'm sorry. I'm sorry. I'm sorry. I'm sorry.",
+                -0.3,
+                0.0,
+            ),
+            scored(" It is very, very fast.", -0.3, 0.0),
+        ]);
+
+        assert_eq!(kept[0].text, " So we move it. I'm sorry.");
+        assert_eq!(kept[1].text, " It is very, very fast.");
+        assert_eq!(
+            actions(&filtered),
+            [(FilterAction::Trimmed, FilterReason::Repetition)]
+        );
+        assert!(filtered[0].segment.text.ends_with("I'm sorry. I'm sorry."));
+    }
+}
@@ -0,0 +1,15 @@
+pub mod chunks;
+pub mod device;
+pub mod filter;
+pub mod options;
+pub mod pool;
+pub mod vad;
+pub mod words;
+
+pub use chunks::*;
+pub use device::*;
+pub use filter::*;
+pub use options::*;
+pub use pool::*;
+pub use vad::*;
+pub use words::*;
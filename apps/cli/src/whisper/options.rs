@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use whisper_rs::{FullParams, SamplingStrategy};
+
+use crate::whisper::Device;
+
+/// whisper.cpp's own default step for retrying a failed decode at a higher temperature
+pub const DEFAULT_TEMPERATURE_INC: f32 = 0.2;
+
+/// How whisper decodes a job's audio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WhisperOptions {
+    /// Spoken language as an ISO code; detected from the audio when `None`
+    pub language: Option<String>,
+    /// Translate the speech to English instead of transcribing it
+    pub translate: bool,
+    /// Beam search width; greedy decoding when `None`
+    pub beam_size: Option<u32>,
+    /// Temperature added on each retry of a segment that fails to decode; 0 disables retries
+    pub temperature_inc: f32,
+    /// Text decoding starts from, to steer spelling of names and jargon
+    pub initial_prompt: Option<String>,
+    /// CPU threads; whisper.cpp picks when `None`
+    pub threads: Option<u32>,
+    /// Device to decode on; the GPU when there is one if `None`
+    pub device: Option<Device>,
+    /// Cut out stretches without speech before decoding, see [`find_non_speech`]
+    ///
+    /// [`find_non_speech`]: crate::whisper::find_non_speech
+    pub vad: bool,
+    /// Drop loops and phantom phrases from the output, see [`filter_segments`]
+    ///
+    /// [`filter_segments`]: crate::whisper::filter_segments
+    pub filter: bool,
+}
+
+impl Default for WhisperOptions {
+    fn default() -> Self {
+        Self {
+            language: None,
+            translate: false,
+            beam_size: None,
+            temperature_inc: DEFAULT_TEMPERATURE_INC,
+            initial_prompt: None,
+            threads: None,
+            device: None,
+            vad: true,
+            filter: true,
+        }
+    }
+}
+
+impl WhisperOptions {
+    /// `whisper.cpp` parameters for these options
+    pub fn full_params(&self) -> FullParams<'_, '_> {
+        let strategy = match self.beam_size {
+            Some(beam_size) => SamplingStrategy::BeamSearch {
+                beam_size: beam_size as i32,
+                patience: -1.0,
+            },
+            None => SamplingStrategy::Greedy { best_of: 5 },
+        };
+
+        let mut params = FullParams::new(strategy);
+        // whisper.cpp assumes English unless told to detect
+        params.set_language(Some(self.language.as_deref().unwrap_or("auto")));
+        params.set_translate(self.translate);
+        params.set_temperature_inc(self.temperature_inc);
+        // per-token timing, grouped into `Segment::words`
+        params.set_token_timestamps(true);
+        if let Some(prompt) = &self.initial_prompt {
+            params.set_initial_prompt(prompt);
+        }
+        if let Some(threads) = self.threads {
+            params.set_n_threads(threads as i32);
+        }
+        params
+    }
+
+    /// Language of the text whisper will produce, when known up front
+    pub fn output_language(&self) -> Option<&str> {
+        if self.translate {
+            Some("en")
+        } else {
+            self.language.as_deref()
+        }
+    }
+
+    /// Short stable key for the options that change the transcript
+    ///
+    /// `None` for the defaults, so transcripts cached before these options
+    /// existed keep their plain `transcript.json` name. Thread count and
+    /// device only affect speed and are left out.
+    pub fn cache_key(&self) -> Option<String> {
+        let decoding = Self {
+            threads: None,
+            device: None,
+            ..self.clone()
+        };
+        if decoding == Self::default() {
+            return None;
+        }
+
+        let json = serde_json::to_string(&decoding).expect("options always serialize");
+        let hash = format!("{:x}", Sha256::digest(json.as_bytes()));
+        Some(hash[..12].to_string())
+    }
+}
+
+/// Clap value parser for `--source-lang`
+pub fn parse_whisper_language(lang: &str) -> Result<String, String> {
+    let lang = lang.to_lowercase();
+    match whisper_rs::get_lang_id(&lang) {
+        Some(_) => Ok(lang),
+        None => Err(format!("{lang} is not a language whisper knows")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_tracks_decoding_options_only() {
+        assert_eq!(WhisperOptions::default().cache_key(), None);
+
+        let threads = WhisperOptions {
+            threads: Some(8),
+            device: Some(Device::Cpu),
+            ..Default::default()
+        };
+        assert_eq!(threads.cache_key(), None);
+
+        let german = WhisperOptions {
+            language: Some("de".to_string()),
+            ..Default::default()
+        };
+        let translated = WhisperOptions {
+            translate: true,
+            ..german.clone()
+        };
+        let key = german.cache_key().unwrap();
+        assert_eq!(key.len(), 12);
+        assert_eq!(german.cache_key(), Some(key));
+        assert_ne!(german.cache_key(), translated.cache_key());
+        assert_eq!(translated.output_language(), Some("en"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError, mpsc},
+    thread,
+};
+
+use tokio::sync::oneshot;
+use whisper_rs::{WhisperContext, WhisperContextParameters};
+
+use crate::whisper::Device;
+
+type Task = Box<dyn FnOnce(&LoadedModels) + Send>;
+
+/// Threads that run whisper, away from the async runtime
+///
+/// Loading a model and decoding keep a core busy for minutes; on a tokio
+/// worker that stalls every other stage and the bus's drain tasks. Models
+/// are loaded once and shared by all of the pool's threads.
+#[derive(Clone)]
+pub struct WhisperPool {
+    tasks: mpsc::Sender<Task>,
+}
+
+impl WhisperPool {
+    /// Start `threads` whisper threads; they exit once every clone of the
+    /// pool is dropped
+    pub fn new(threads: usize) -> Self {
+        let (tasks, receiver) = mpsc::channel::<Task>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let models = Arc::new(LoadedModels::default());
+
+        for i in 0..threads.max(1) {
+            let receiver = Arc::clone(&receiver);
+            let models = Arc::clone(&models);
+            thread::Builder::new()
+                .name(format!("whisper-{i}"))
+                .spawn(move || {
+                    loop {
+                        let task = receiver
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .recv();
+                        let Ok(task) = task else { break };
+                        // a panicking job fails on its own; the thread keeps serving
+                        let _ = catch_unwind(AssertUnwindSafe(|| task(&models)));
+                    }
+                })
+                .expect("failed to spawn whisper thread");
+        }
+
+        Self { tasks }
+    }
+
+    /// Run `f` on a pool thread and wait for its result
+    pub async fn run<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&LoadedModels) -> T + Send + 'static,
+    ) -> anyhow::Result<T> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tasks
+            .send(Box::new(move |models| {
+                let _ = result_tx.send(f(models));
+            }))
+            .map_err(|_| anyhow::anyhow!("whisper pool has shut down"))?;
+        result_rx
+            .await
+            .map_err(|_| anyhow::anyhow!("whisper thread panicked"))
+    }
+}
+
+/// Whisper models loaded so far, by path and device
+#[derive(Default)]
+pub struct LoadedModels {
+    contexts: Mutex<HashMap<(PathBuf, Device), ModelSlot>>,
+}
+
+/// One model, empty until it has loaded
+type ModelSlot = Arc<Mutex<Option<Arc<WhisperContext>>>>;
+
+impl LoadedModels {
+    /// The model at `path` on `device`, loaded from disk on first use
+    pub fn get(&self, path: &Path, device: Device) -> anyhow::Result<Arc<WhisperContext>> {
+        let slot = Arc::clone(
+            self.contexts
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry((path.to_path_buf(), device))
+                .or_default(),
+        );
+        // only jobs waiting for this same model block while it loads
+        let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(context) = slot.as_ref() {
+            return Ok(Arc::clone(context));
+        }
+
+        let params = WhisperContextParameters {
+            use_gpu: device == Device::Gpu,
+            flash_attn: device == Device::Gpu,
+            ..Default::default()
+        };
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("model path {} is not UTF-8", path.display()))?;
+        let context = Arc::new(WhisperContext::new_with_params(path_str, params)?);
+        *slot = Some(Arc::clone(&context));
+        Ok(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn survives_a_panicking_job() {
+        let pool = WhisperPool::new(1);
+
+        let failed: anyhow::Result<u32> = pool.run(|_| panic!("whisper blew up")).await;
+        assert!(failed.is_err());
+
+        let on_pool_thread = pool
+            .run(|_| thread::current().name().map(str::to_string))
+            .await
+            .unwrap();
+        assert_eq!(on_pool_thread.as_deref(), Some("whisper-0"));
+    }
+}
@@ -0,0 +1,173 @@
+use std::ops::Range;
+
+use crate::{types::Segment, whisper::SAMPLE_RATE};
+
+/// 30 ms, the frame size WebRTC's VAD uses
+const FRAME_SAMPLES: usize = SAMPLE_RATE * 3 / 100;
+
+/// Shorter pauses stay in the audio; whisper segments better with them
+const MIN_NON_SPEECH_SECONDS: f64 = 2.0;
+
+/// Silence kept on either side of speech, so word edges aren't clipped
+const PADDING_SECONDS: f64 = 0.3;
+
+/// Speech sits this far above the quietest frames of the window
+const SPEECH_ABOVE_FLOOR_DB: f32 = 12.0;
+
+/// Frames quieter than this are never speech, frames louder always are
+const SILENCE_DB: f32 = -55.0;
+const SPEECH_DB: f32 = -35.0;
+
+/// Stretches of `samples` with no speech, as sample ranges
+///
+/// Energy based: a frame is speech when it is well above the noise floor of
+/// the window, estimated from its quietest tenth. This catches dead air and
+/// quiet music beds; music at speech level is left to whisper.
+pub fn find_non_speech(samples: &[f32]) -> Vec<Range<usize>> {
+    let levels: Vec<f32> = samples.chunks(FRAME_SAMPLES).map(frame_db).collect();
+    if levels.is_empty() {
+        return Vec::new();
+    }
+
+    let mut sorted = levels.clone();
+    sorted.sort_by(f32::total_cmp);
+    let floor = sorted[sorted.len() / 10];
+    let threshold = (floor + SPEECH_ABOVE_FLOOR_DB).clamp(SILENCE_DB, SPEECH_DB);
+
+    let min_frames = (MIN_NON_SPEECH_SECONDS * SAMPLE_RATE as f64) as usize / FRAME_SAMPLES;
+    let padding = (PADDING_SECONDS * SAMPLE_RATE as f64) as usize;
+
+    let mut spans = Vec::new();
+    let mut quiet_since = None;
+    for (i, &level) in levels.iter().chain([&f32::INFINITY]).enumerate() {
+        match (level < threshold, quiet_since) {
+            (true, None) => quiet_since = Some(i),
+            (false, Some(first)) => {
+                quiet_since = None;
+                if i - first < min_frames {
+                    continue;
+                }
+                let start = if first == 0 {
+                    0
+                } else {
+                    first * FRAME_SAMPLES + padding
+                };
+                let end = if i == levels.len() {
+                    samples.len()
+                } else {
+                    i * FRAME_SAMPLES - padding
+                };
+                spans.push(start..end);
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+fn frame_db(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+    (10.0 * mean_square.log10()).max(-100.0)
+}
+
+/// Audio with the non-speech stretches cut out, remembering where each piece
+/// came from
+pub struct SpeechAudio {
+    pub samples: Vec<f32>,
+    /// `(start in samples, start in original)` of each kept piece
+    pieces: Vec<(usize, usize)>,
+}
+
+impl SpeechAudio {
+    pub fn new(original: &[f32], non_speech: &[Range<usize>]) -> Self {
+        let mut samples = Vec::with_capacity(original.len());
+        let mut pieces = Vec::new();
+        let mut from = 0;
+        for skip in non_speech.iter().chain([&(original.len()..original.len())]) {
+            if skip.start > from {
+                pieces.push((samples.len(), from));
+                samples.extend_from_slice(&original[from..skip.start]);
+            }
+            from = skip.end;
+        }
+        Self { samples, pieces }
+    }
+
+    /// Shift segments decoded from the cut audio back to original time
+    pub fn restore_times(&self, segments: Vec<Segment>) -> Vec<Segment> {
+        segments
+            .into_iter()
+            .map(|segment| self.restore_segment(segment))
+            .collect()
+    }
+
+    pub fn restore_segment(&self, mut segment: Segment) -> Segment {
+        segment.start = self.to_original(segment.start);
+        segment.end = self.to_original(segment.end);
+        for word in &mut segment.words {
+            word.start = self.to_original(word.start);
+            word.end = self.to_original(word.end);
+        }
+        segment
+    }
+
+    fn to_original(&self, seconds: f64) -> f64 {
+        let sample = (seconds * SAMPLE_RATE as f64) as usize;
+        let (start, original) = self
+            .pieces
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= sample)
+            .copied()
+            .unwrap_or((0, 0));
+        seconds + (original as f64 - start as f64) / SAMPLE_RATE as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(seconds: usize) -> Vec<f32> {
+        (0..seconds * SAMPLE_RATE)
+            .map(|i| ((i % 64) as f32 / 64.0 - 0.5) * 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn finds_long_silences_only() {
+        let mut samples = vec![0.0; 3 * SAMPLE_RATE];
+        samples.extend(tone(5));
+        samples.extend(vec![0.0; SAMPLE_RATE]);
+        samples.extend(tone(5));
+        samples.extend(vec![0.0; 10 * SAMPLE_RATE]);
+        samples.extend(tone(5));
+
+        let spans = find_non_speech(&samples);
+        let tenths = |sample: usize| (sample as f64 / SAMPLE_RATE as f64 * 10.0).round() / 10.0;
+        let seconds: Vec<(f64, f64)> = spans
+            .iter()
+            .map(|r| (tenths(r.start), tenths(r.end)))
+            .collect();
+        assert_eq!(seconds, [(0.0, 2.7), (14.3, 23.7)]);
+    }
+
+    #[test]
+    fn maps_cut_audio_back_to_original_time() {
+        let original = vec![0.0; 30 * SAMPLE_RATE];
+        let audio = SpeechAudio::new(
+            &original,
+            &[0..5 * SAMPLE_RATE, 10 * SAMPLE_RATE..20 * SAMPLE_RATE],
+        );
+        assert_eq!(audio.samples.len(), 15 * SAMPLE_RATE);
+
+        let segments = audio.restore_times(vec![Segment {
+            start: 1.0,
+            end: 7.0,
+            text: String::new(),
+            words: Vec::new(),
+            speaker: None,
+        }]);
+        assert_eq!((segments[0].start, segments[0].end), (6.0, 22.0));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::types::Word;
+
+/// A text token whisper decoded, with its timing in seconds
+pub struct TimedToken<'a> {
+    /// Raw bytes; a multi-byte character may be split across tokens
+    pub bytes: &'a [u8],
+    pub start: f64,
+    pub end: f64,
+    pub probability: f32,
+}
+
+/// Join tokens into words
+///
+/// A token starting with a space begins a new word, everything else is
+/// appended to the current one. Punctuation stays attached to the word it
+/// follows.
+pub fn group_words<'a>(tokens: impl IntoIterator<Item = TimedToken<'a>>) -> Vec<Word> {
+    struct Pending {
+        bytes: Vec<u8>,
+        start: f64,
+        end: f64,
+        probabilities: Vec<f32>,
+    }
+
+    fn finish(pending: Pending) -> Option<Word> {
+        let text = String::from_utf8_lossy(&pending.bytes).into_owned();
+        if text.trim().is_empty() {
+            return None;
+        }
+        let probability =
+            pending.probabilities.iter().sum::<f32>() / pending.probabilities.len() as f32;
+        Some(Word {
+            start: pending.start,
+            end: pending.end,
+            text,
+            probability,
+        })
+    }
+
+    let mut words = Vec::new();
+    let mut current: Option<Pending> = None;
+
+    for token in tokens {
+        if token.bytes.is_empty() {
+            continue;
+        }
+        match current.as_mut() {
+            Some(pending) if !token.bytes.starts_with(b" ") => {
+                pending.bytes.extend_from_slice(token.bytes);
+                pending.end = pending.end.max(token.end);
+                pending.probabilities.push(token.probability);
+            }
+            _ => {
+                words.extend(current.take().and_then(finish));
+                current = Some(Pending {
+                    bytes: token.bytes.to_vec(),
+                    start: token.start,
+                    end: token.end,
+                    probabilities: vec![token.probability],
+                });
+            }
+        }
+    }
+    words.extend(current.and_then(finish));
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(text: &'static str, start: f64, end: f64, probability: f32) -> TimedToken<'static> {
+        TimedToken {
+            bytes: text.as_bytes(),
+            start,
+            end,
+            probability,
+        }
+    }
+
+    #[test]
+    fn joins_tokens_into_words() {
+        let words = group_words([
+            token(" Own", 0.0, 0.3, 0.9),
+            token("ership", 0.3, 0.6, 0.5),
+            token(" matters", 0.6, 1.0, 0.8),
+            token(".", 1.0, 1.1, 1.0),
+        ]);
+
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, [" Ownership", " matters."]);
+        assert_eq!((words[0].start, words[0].end), (0.0, 0.6));
+        assert!((words[0].probability - 0.7).abs() < 1e-6);
+        assert_eq!(words[1].end, 1.1);
+    }
+
+    #[test]
+    fn rejoins_characters_split_across_tokens() {
+        let bytes = " привет".as_bytes();
+        let words = group_words([
+            TimedToken {
+                bytes: &bytes[..4],
+                start: 0.0,
+                end: 0.2,
+                probability: 1.0,
+            },
+            TimedToken {
+                bytes: &bytes[4..],
+                start: 0.2,
+                end: 0.5,
+                probability: 1.0,
+            },
+        ]);
+
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].text, " привет");
+    }
+}
@@ -1,22 +1,40 @@
 use std::sync::Arc;
 
 use bratishka_core::{
-    events::expect,
-    queues::{FifoDropOldestQueue, QueueKind},
+    events::{downcast_ref, expect},
+    queues::QueueKind,
     workers::{InputSpec, SubscriptionSpec, Worker},
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use tokio::{sync::Semaphore, task::JoinSet};
 
 use crate::{
-    provider::{Provider, ProviderError},
+    chunking::{TranscriptChunk, chunk_segments, merge_chunk_sections},
+    llm::{
+        JobUsage, JsonSchema, LlmClient, LlmRequest, TranscriptInput, UsageTracker, merge_passages,
+        prompt_line_tokens,
+    },
+    media::{VideoMetadata, format_chapters},
+    timestamp::format_clock,
     types::Transcript,
-    workers::events::{AudioTranscribed, SectionsAnalyzed, SourceSection},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{AudioTranscribed, SectionsAnalyzed, SourceSection, SpeakersAssigned},
+    },
 };
 
+/// Token budget for the transcript part of a single analysis request
+const CHUNK_MAX_TOKENS: usize = 12_000;
+/// How much of the previous chunk is repeated at the start of the next one
+const CHUNK_OVERLAP_SECONDS: f64 = 60.0;
+const MAX_PARALLEL_CHUNKS: usize = 4;
+/// Transcript segments are merged into prompt lines covering up to this long
+const PROMPT_LINE_SECONDS: f64 = 20.0;
+
 static SECTIONS_ANALYSIS_PROMPT: &str = r#"
   You are a video content analyzer. You have access to web search to enrich your analysis.
 
-  INPUT: Video transcript with timestamps in format [MM:SS] text
+  INPUT: Video transcript, one passage per line in format [HH:MM:SS] text
 
   TASK:
   1. Identify logical sections based on topic changes
@@ -24,19 +42,21 @@ static SECTIONS_ANALYSIS_PROMPT: &str = r#"
   references mentioned
   3. Create detailed summaries that combine transcript content with external knowledge
 
-  OUTPUT: Return ONLY valid JSON array:
-  [
-    {
-      "name": "Section title",
-      "content": "Raw transcript text for this section",
-      "started_at": 0.0,
-      "ended_at": 125.5,
-      "key_concepts": ["concept1", "concept2"],
-      "external_context": "Relevant background from web search",
-      "summary": "1-2 paragraph detailed summary. Explain technical terms. Include context
+  OUTPUT: Return ONLY valid JSON object:
+  {
+    "sections": [
+      {
+        "name": "Section title",
+        "content": "Raw transcript text for this section",
+        "started_at": 0.0,
+        "ended_at": 125.5,
+        "key_concepts": ["concept1", "concept2"],
+        "external_context": "Relevant background from web search",
+        "summary": "1-2 paragraph detailed summary. Explain technical terms. Include context
   not in transcript. Connect concepts to broader knowledge."
-    }
-  ]
+      }
+    ]
+  }
 
   RULES:
   - Identify 3-10 sections based on topic changes
@@ -46,28 +66,37 @@ static SECTIONS_ANALYSIS_PROMPT: &str = r#"
     - References to events, people, companies
     - Concepts that need explanation
   - Sections must be sequential and cover entire video
+  - started_at and ended_at are seconds from the start of the video ([00:02:05] is 125)
+  - When creator chapters are given, start a section at every chapter boundary and reuse the
+    chapter titles; split a chapter that covers several topics, but never merge chapters
   - Summary should educate, not just describe
+  - When segments carry a "speaker" label, attribute statements in summaries ("Speaker 1
+    argues..., Speaker 2 disagrees..."); once the conversation makes roles or names clear, use
+    them instead ("the host", "the guest")
 "#;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct AnalyzeSectionsWorker;
 
-#[derive(Debug, thiserror::Error)]
-pub enum InteligenceError {
-    #[error("Provider error: {0}")]
-    ProviderError(#[from] ProviderError),
-
-    #[error("Invalid API response: {0}")]
-    InvalidApiResponse(serde_json::Value),
-
-    #[error("HTTP error: {0}")]
-    HttpError(#[from] reqwest::Error),
+/// Structured outputs need an object at the top level, so sections are wrapped
+#[derive(Deserialize)]
+struct AnalyzedSections {
+    sections: Vec<SourceSection>,
+}
 
-    #[error("Failed to parse JSON: {0}")]
-    JsonError(#[from] serde_json::Error),
+impl JsonSchema for AnalyzedSections {
+    const SCHEMA_NAME: &'static str = "analyzed_sections";
 
-    #[error("Failed to process sections: {reason}")]
-    ProcessSectionsFailed { reason: String },
+    fn json_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "sections": { "type": "array", "items": SourceSection::json_schema() }
+            },
+            "required": ["sections"],
+            "additionalProperties": false
+        })
+    }
 }
 
 impl AnalyzeSectionsWorker {
@@ -75,52 +104,121 @@ impl AnalyzeSectionsWorker {
         Self
     }
 
+    /// Map-reduce over the transcript: analyze overlapping chunks in parallel,
+    /// then stitch the per-chunk sections back into one timeline
     async fn analyze_sections(
-        provider: &Provider,
+        client: Arc<LlmClient>,
         transcript: &Transcript,
+        metadata: Option<&VideoMetadata>,
     ) -> anyhow::Result<Vec<SourceSection>> {
-        let config = provider.config();
-        let api_key = provider.validate_api_key()?;
-        let user_prompt = format!(
-            "Attaching the transcript and timestamps. {}",
-            serde_json::to_string_pretty(transcript)?
+        let passages = merge_passages(&transcript.segments, PROMPT_LINE_SECONDS);
+        let chunks = chunk_segments(
+            &passages,
+            CHUNK_MAX_TOKENS,
+            CHUNK_OVERLAP_SECONDS,
+            prompt_line_tokens,
+        );
+        let total = chunks.len();
+        let inputs: Vec<TranscriptInput> = chunks
+            .iter()
+            .map(|chunk| TranscriptInput::new(&chunk.segments))
+            .collect();
+        println!(
+            "analyzing {} transcript lines (~{} tokens) in {} request(s)",
+            inputs.iter().map(|i| i.lines).sum::<usize>(),
+            inputs.iter().map(|i| i.tokens).sum::<usize>(),
+            total
         );
+        let permits = Arc::new(Semaphore::new(MAX_PARALLEL_CHUNKS));
 
-        let response = reqwest::Client::new()
-            .post(config.api_url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .json(&serde_json::json!({
-                "model": config.model,
-                "tools": [{"type": "web_search"}],
-                "input": [
-                    {
-                        "role": "system",
-                        "content": SECTIONS_ANALYSIS_PROMPT,
-                    },
-                    {
-                        "role": "user",
-                        "content": user_prompt,
-                    },
-                ],
-                "temperature": 0.3,
-            }))
-            .send()
+        let mut tasks = JoinSet::new();
+        for (chunk, input) in chunks.into_iter().zip(inputs) {
+            let client = Arc::clone(&client);
+            let language = transcript.language.clone();
+            let context = metadata.map(|m| Self::video_context(m, &chunk));
+            let permits = Arc::clone(&permits);
+
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                let sections =
+                    Self::analyze_chunk(&client, &chunk, &input, total, &language, context).await?;
+                anyhow::Ok((chunk, sections))
+            });
+        }
+
+        let mut results = Vec::with_capacity(total);
+        while let Some(result) = tasks.join_next().await {
+            results.push(result??);
+        }
+        results.sort_by_key(|(chunk, _)| chunk.index);
+
+        let chapter_starts: Vec<f64> = metadata
+            .map(|m| m.chapters.iter().map(|c| c.start_seconds).collect())
+            .unwrap_or_default();
+        Ok(merge_chunk_sections(results, &chapter_starts))
+    }
+
+    async fn analyze_chunk(
+        client: &LlmClient,
+        chunk: &TranscriptChunk,
+        input: &TranscriptInput,
+        total_chunks: usize,
+        language: &str,
+        video_context: Option<String>,
+    ) -> anyhow::Result<Vec<SourceSection>> {
+        let span = format!(
+            "{}–{}",
+            format_clock(chunk.start()),
+            format_clock(chunk.end())
+        );
+        let user_prompt = if total_chunks == 1 {
+            format!(
+                "Transcript ({span}, language: {language}):\n\n{}",
+                input.text
+            )
+        } else {
+            format!(
+                "Part {} of {} of the transcript ({span}, language: {language}). \
+                 Identify sections for this part only and cover it from start to end.\n\n{}",
+                chunk.index + 1,
+                total_chunks,
+                input.text
+            )
+        };
+
+        let user_prompt = match video_context {
+            Some(context) if !context.is_empty() => format!("{context}\n\n{user_prompt}"),
+            _ => user_prompt,
+        };
+
+        let analyzed: AnalyzedSections = client
+            .complete_structured(LlmRequest {
+                system: SECTIONS_ANALYSIS_PROMPT.to_string(),
+                user: user_prompt,
+                temperature: 0.3,
+                web_search: true,
+            })
             .await?;
-        println!("RESPONSE: {:?}", response);
 
-        let response = response.json::<serde_json::Value>().await?;
+        Ok(analyzed.sections)
+    }
 
-        // Extract content from response - /v1/responses format
-        let content = response["output"]
-            .as_array()
-            .and_then(|arr| arr.iter().rev().find(|item| item["type"] == "message"))
-            .and_then(|msg| msg["content"][0]["text"].as_str())
-            .ok_or_else(|| InteligenceError::ProcessSectionsFailed {
-                reason: format!("Invalid API response structure: {:?}", response),
-            })?;
+    /// What the uploader said about the video, plus the chapters inside `chunk`
+    fn video_context(metadata: &VideoMetadata, chunk: &TranscriptChunk) -> String {
+        let mut context = metadata.prompt_context();
+        if !context.is_empty() {
+            context.insert_str(0, "About the video:\n");
+        }
 
-        Ok(serde_json::from_str(content)?)
+        let chapters = metadata.chapters_between(chunk.start(), chunk.end());
+        if !chapters.is_empty() {
+            if !context.is_empty() {
+                context.push_str("\n\n");
+            }
+            context.push_str("Creator chapters:\n");
+            context.push_str(&format_chapters(&chapters));
+        }
+        context
     }
 }
 
@@ -130,10 +228,20 @@ impl Worker for AnalyzeSectionsWorker {
     fn subscription() -> bratishka_core::workers::SubscriptionSpec {
         SubscriptionSpec {
             subscriber_id: Self::SUBSCRIBER_ID,
-            inputs: vec![InputSpec {
-                event_type: AudioTranscribed::EVENT_TYPE,
-                queue_kind: QueueKind::FifoDropOldest { capacity: 4 },
-            }],
+            inputs: vec![
+                InputSpec {
+                    event_type: AudioTranscribed::EVENT_TYPE,
+                    queue_kind: QueueKind::FifoDropOldest {
+                        capacity: STAGE_QUEUE_CAPACITY,
+                    },
+                },
+                InputSpec {
+                    event_type: SpeakersAssigned::EVENT_TYPE,
+                    queue_kind: QueueKind::FifoDropOldest {
+                        capacity: STAGE_QUEUE_CAPACITY,
+                    },
+                },
+            ],
         }
     }
 
@@ -142,16 +250,80 @@ impl Worker for AnalyzeSectionsWorker {
         event: std::sync::Arc<bratishka_core::events::EnrichedEvent>,
         bus: &bratishka_core::events::EventBus,
     ) -> anyhow::Result<()> {
-        let req = expect::<AudioTranscribed>(&event.event, AudioTranscribed::EVENT_TYPE)?;
-        let sections = Self::analyze_sections(&req.job.provider, &req.transcript).await?;
+        let (job, transcript) = if let Some(req) = downcast_ref::<SpeakersAssigned>(&event.event) {
+            (&req.job, &req.transcript)
+        } else {
+            let req = expect::<AudioTranscribed>(&event.event, AudioTranscribed::EVENT_TYPE)?;
+            // diarized jobs come back through SpeakersAssigned
+            if req.job.diarize {
+                return Ok(());
+            }
+            (&req.job, &req.transcript)
+        };
+        let tracker = Arc::new(UsageTracker::new(JobUsage::default(), job.max_cost_usd));
+        let client = Arc::new(LlmClient::for_job(
+            job,
+            Self::SUBSCRIBER_ID,
+            Arc::clone(&tracker),
+        ));
+        let sections = Self::analyze_sections(client, transcript, job.metadata.as_ref()).await?;
 
         bus.publish(Arc::new(SectionsAnalyzed::new(
             event.event.event_id(),
-            req.job.clone(),
+            job.clone(),
             sections,
-            req.transcript.clone(),
+            transcript.clone(),
+            tracker.usage(),
         )));
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        media::Chapter,
+        provider::Provider,
+        testing::{
+            CaptureBus, MockLlmServer, MockReply, SECTIONS_FIXTURE, TRANSCRIPT_FIXTURE,
+            transcribed_job,
+        },
+    };
+
+    #[tokio::test]
+    async fn metadata_and_chapters_reach_the_prompt() {
+        let server = MockLlmServer::start().await;
+        server
+            .script("", vec![MockReply::content(SECTIONS_FIXTURE)])
+            .await;
+        let root = tempfile::tempdir().unwrap();
+        let mut job = transcribed_job(Provider::Grok, root.path(), &server.base_url());
+        job.metadata = Some(VideoMetadata {
+            uploader: Some("Rustacean Station".to_string()),
+            chapters: vec![Chapter {
+                start_seconds: 0.0,
+                end_seconds: 30.0,
+                title: "Moves and borrows".to_string(),
+            }],
+            ..Default::default()
+        });
+        let transcript: Transcript = serde_json::from_str(TRANSCRIPT_FIXTURE).unwrap();
+        let mut bus = CaptureBus::new(&[SectionsAnalyzed::EVENT_TYPE]);
+
+        bus.deliver(
+            &mut AnalyzeSectionsWorker::new(),
+            Arc::new(AudioTranscribed::new(Uuid::new_v4(), job, transcript)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(bus.take().len(), 1);
+        let prompt = server.requests().await[0].1.to_string();
+        assert!(prompt.contains("Channel: Rustacean Station"));
+        assert!(prompt.contains("[00:00–00:30] Moves and borrows"));
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use bratishka_core::{
+    events::{EnrichedEvent, EventBus, downcast_ref},
+    queues::QueueKind,
+    workers::{InputSpec, PipelineFailed, SubscriptionSpec, Worker},
+};
+use tokio::{
+    fs,
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+use uuid::Uuid;
+
+use crate::{
+    cache::get_batch_index_path,
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{
+            BatchCompleted, BatchIndex, BatchIndexEntry, BatchPlanned, JobSpec, ReportCompiled,
+        },
+    },
+};
+
+/// Collects the outcome of every job in a batch and writes the batch index
+/// once the last one has finished or failed
+pub struct BatchIndexWorker {
+    batches: HashMap<Uuid, OpenBatch>,
+    /// Batch of every planned job still running
+    job_batches: HashMap<Uuid, Uuid>,
+    /// Outcomes that arrived before their batch plan was seen
+    early: HashMap<Uuid, JobOutcome>,
+    jobs: BatchJobs,
+}
+
+/// Jobs of published batch plans, shared by the playlist expansion and the index
+///
+/// The expansion marks a batch's jobs before publishing anything, so the index
+/// only holds back outcomes it will be able to place; the rest belong to
+/// single-video jobs and are ignored. Each job also holds one of a fixed number
+/// of slots from when it is published until the index records its outcome, so
+/// a long playlist never floods the drop-oldest stage queues.
+#[derive(Clone)]
+pub struct BatchJobs {
+    pending: Arc<Mutex<HashSet<Uuid>>>,
+    running: Arc<Mutex<HashMap<Uuid, OwnedSemaphorePermit>>>,
+    slots: Arc<Semaphore>,
+}
+
+impl BatchJobs {
+    /// Allow `max_running` batch jobs in the pipeline at once
+    pub fn new(max_running: usize) -> Self {
+        Self {
+            pending: Arc::default(),
+            running: Arc::default(),
+            slots: Arc::new(Semaphore::new(max_running.max(1))),
+        }
+    }
+
+    pub fn mark(&self, job_ids: impl IntoIterator<Item = Uuid>) {
+        lock(&self.pending).extend(job_ids);
+    }
+
+    /// Wait for a free slot and hold it for `job_id` until its outcome is recorded
+    pub async fn start(&self, job_id: Uuid) {
+        let permit = Arc::clone(&self.slots)
+            .acquire_owned()
+            .await
+            .expect("batch slots are never closed");
+        lock(&self.running).insert(job_id, permit);
+    }
+
+    fn finish(&self, job_id: &Uuid) {
+        lock(&self.running).remove(job_id);
+    }
+
+    fn contains(&self, job_id: &Uuid) -> bool {
+        lock(&self.pending).contains(job_id)
+    }
+
+    fn unmark(&self, job_id: &Uuid) {
+        lock(&self.pending).remove(job_id);
+    }
+}
+
+impl Default for BatchJobs {
+    fn default() -> Self {
+        Self::new(STAGE_QUEUE_CAPACITY)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct OpenBatch {
+    parent_event_id: Uuid,
+    job: JobSpec,
+    index: BatchIndex,
+    /// Position of each job's entry in `index.entries`
+    positions: HashMap<Uuid, usize>,
+    remaining: usize,
+}
+
+enum JobOutcome {
+    Completed {
+        report_path: std::path::PathBuf,
+        report_title: String,
+        cost_usd: f64,
+    },
+    Failed(String),
+}
+
+impl BatchIndexWorker {
+    pub fn new(jobs: BatchJobs) -> Self {
+        Self {
+            batches: HashMap::new(),
+            job_batches: HashMap::new(),
+            early: HashMap::new(),
+            jobs,
+        }
+    }
+
+    fn open(&mut self, event_id: Uuid, req: &BatchPlanned) {
+        let plan = &req.plan;
+        let mut batch = OpenBatch {
+            parent_event_id: event_id,
+            job: req.job.clone(),
+            index: BatchIndex {
+                batch_id: plan.batch_id,
+                source_url: plan.source_url.clone(),
+                title: plan.title.clone(),
+                entries: Vec::with_capacity(plan.jobs.len()),
+            },
+            positions: HashMap::new(),
+            remaining: plan.jobs.len(),
+        };
+
+        for planned in &plan.jobs {
+            batch
+                .positions
+                .insert(planned.job_id, batch.index.entries.len());
+            batch.index.entries.push(BatchIndexEntry {
+                url: planned.url.clone(),
+                title: planned.title.clone(),
+                upload_date: planned.upload_date.clone(),
+                report_path: None,
+                report_title: None,
+                cost_usd: 0.0,
+                error: None,
+            });
+            self.job_batches.insert(planned.job_id, plan.batch_id);
+            self.jobs.unmark(&planned.job_id);
+        }
+        self.batches.insert(plan.batch_id, batch);
+
+        let early: Vec<Uuid> = plan
+            .jobs
+            .iter()
+            .map(|j| j.job_id)
+            .filter(|id| self.early.contains_key(id))
+            .collect();
+        for job_id in early {
+            let outcome = self.early.remove(&job_id).unwrap();
+            self.record(job_id, outcome);
+        }
+    }
+
+    fn record(&mut self, job_id: Uuid, outcome: JobOutcome) {
+        self.jobs.finish(&job_id);
+        let Some(batch_id) = self.job_batches.remove(&job_id) else {
+            if self.jobs.contains(&job_id) {
+                self.early.insert(job_id, outcome);
+            }
+            return;
+        };
+        let Some(batch) = self.batches.get_mut(&batch_id) else {
+            return;
+        };
+
+        let entry = &mut batch.index.entries[batch.positions[&job_id]];
+        match outcome {
+            JobOutcome::Completed {
+                report_path,
+                report_title,
+                cost_usd,
+            } => {
+                entry.report_path = Some(report_path);
+                entry.report_title = Some(report_title);
+                entry.cost_usd = cost_usd;
+            }
+            JobOutcome::Failed(error) => entry.error = Some(error),
+        }
+        batch.remaining -= 1;
+    }
+
+    async fn close_finished(&mut self, bus: &EventBus) -> anyhow::Result<()> {
+        let finished: Vec<Uuid> = self
+            .batches
+            .iter()
+            .filter(|(_, b)| b.remaining == 0)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for batch_id in finished {
+            let batch = self.batches.remove(&batch_id).unwrap();
+            let index_path = get_batch_index_path(&batch.job.root_cache_dir, &batch_id);
+            if let Some(dir) = index_path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            fs::write(&index_path, serde_json::to_string_pretty(&batch.index)?).await?;
+
+            bus.publish(Arc::new(BatchCompleted::new(
+                batch.parent_event_id,
+                batch.job,
+                batch.index,
+                index_path,
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Worker for BatchIndexWorker {
+    const SUBSCRIBER_ID: &'static str = "batch.index";
+
+    fn subscription() -> SubscriptionSpec {
+        let queue_kind = || QueueKind::FifoDropOldest {
+            capacity: STAGE_QUEUE_CAPACITY,
+        };
+        SubscriptionSpec {
+            subscriber_id: Self::SUBSCRIBER_ID,
+            inputs: vec![
+                InputSpec {
+                    event_type: BatchPlanned::EVENT_TYPE,
+                    queue_kind: queue_kind(),
+                },
+                InputSpec {
+                    event_type: ReportCompiled::EVENT_TYPE,
+                    queue_kind: queue_kind(),
+                },
+                InputSpec {
+                    event_type: PipelineFailed::EVENT_TYPE,
+                    queue_kind: queue_kind(),
+                },
+            ],
+        }
+    }
+
+    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
+        if let Some(req) = downcast_ref::<BatchPlanned>(&event.event) {
+            self.open(event.event.event_id(), req);
+        }
+
+        if let Some(req) = downcast_ref::<ReportCompiled>(&event.event)
+            && req.job.batch_id.is_some()
+        {
+            self.record(
+                req.job.job_id,
+                JobOutcome::Completed {
+                    report_path: req.report_path.clone(),
+                    report_title: req.report.title.clone(),
+                    cost_usd: req.usage.total_cost_usd(),
+                },
+            );
+        }
+
+        // failures can't tell whether their job is batched; `record` drops those that aren't
+        if let Some(req) = downcast_ref::<PipelineFailed>(&event.event)
+            && let Some(job_id) = req.correlation_id
+        {
+            self.record(
+                job_id,
+                JobOutcome::Failed(format!("{}: {}", req.stage, req.message)),
+            );
+        }
+
+        self.close_finished(bus).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        provider::Provider,
+        testing::transcribed_job,
+        workers::events::{BatchPlan, PlannedJob},
+    };
+
+    fn failed() -> JobOutcome {
+        JobOutcome::Failed("youtube.download_video: offline".to_string())
+    }
+
+    #[test]
+    fn ignores_failures_of_single_jobs() {
+        let mut worker = BatchIndexWorker::new(BatchJobs::default());
+        worker.record(Uuid::new_v4(), failed());
+        assert!(worker.early.is_empty());
+    }
+
+    #[test]
+    fn keeps_early_outcomes_until_the_plan_arrives() {
+        let jobs = BatchJobs::default();
+        let mut worker = BatchIndexWorker::new(jobs.clone());
+        let job_id = Uuid::new_v4();
+        jobs.mark([job_id]);
+
+        worker.record(job_id, failed());
+        assert_eq!(worker.early.len(), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let plan = BatchPlan {
+            batch_id: Uuid::new_v4(),
+            source_url: "https://www.youtube.com/playlist?list=offline".to_string(),
+            title: None,
+            jobs: vec![PlannedJob {
+                job_id,
+                url: "https://www.youtube.com/watch?v=offline".to_string(),
+                title: None,
+                upload_date: None,
+            }],
+        };
+        let job = transcribed_job(Provider::Grok, dir.path(), "http://127.0.0.1:9");
+        worker.open(
+            Uuid::new_v4(),
+            &BatchPlanned::new(Uuid::new_v4(), job, plan),
+        );
+
+        assert!(worker.early.is_empty());
+        assert!(!jobs.contains(&job_id));
+        let batch = worker.batches.values().next().unwrap();
+        assert_eq!(batch.remaining, 0);
+        assert!(batch.index.entries[0].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn recorded_outcomes_free_their_slot() {
+        let jobs = BatchJobs::new(1);
+        let mut worker = BatchIndexWorker::new(jobs.clone());
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        jobs.mark([first, second]);
+
+        jobs.start(first).await;
+        let waiting = tokio::spawn({
+            let jobs = jobs.clone();
+            async move { jobs.start(second).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        worker.record(first, failed());
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiting)
+            .await
+            .expect("slot was not freed")
+            .unwrap();
+    }
+}
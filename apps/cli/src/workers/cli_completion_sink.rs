@@ -1,21 +1,59 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use bratishka_core::{
-    events::{EnrichedEvent, EventBus, downcast_ref, expect},
+    events::{EnrichedEvent, EventBus, downcast_ref},
     queues::QueueKind,
     workers::{InputSpec, PipelineFailed, SubscriptionSpec, Worker},
 };
-use tokio::sync::oneshot;
+use tokio::sync::mpsc;
+use uuid::Uuid;
 
-use crate::{types::VideoReport, workers::events::ReportCompiled};
+use crate::{
+    llm::JobUsage,
+    types::VideoReport,
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{BatchCompleted, BatchIndex, BatchPlanned, ReportCompiled},
+    },
+};
+
+/// What the CLI gets back once a job's report is ready
+pub struct CompletedJob {
+    pub job_id: Uuid,
+    pub report: VideoReport,
+    pub report_path: PathBuf,
+    pub markdown_path: PathBuf,
+    pub usage: JobUsage,
+    /// Stages that reused cached artifacts
+    pub cache_hits: Vec<String>,
+}
+
+/// What the CLI gets back once every job of a batch has finished
+pub struct CompletedBatch {
+    /// The playlist job the batch was expanded from
+    pub job_id: Uuid,
+    pub index: BatchIndex,
+    pub index_path: PathBuf,
+}
+
+/// Everything the pipeline reports back to its caller
+pub enum PipelineOutcome {
+    Job(Result<CompletedJob, PipelineFailed>),
+    /// The jobs a playlist job expanded into, sent before any of them finishes
+    Planned {
+        job_id: Uuid,
+        job_ids: Vec<Uuid>,
+    },
+    Batch(CompletedBatch),
+}
 
 pub struct CliCompletionSinkWorker {
-    done: Option<oneshot::Sender<Result<VideoReport, PipelineFailed>>>,
+    outcomes: mpsc::UnboundedSender<PipelineOutcome>,
 }
 
 impl CliCompletionSinkWorker {
-    pub fn new(done: Option<oneshot::Sender<Result<VideoReport, PipelineFailed>>>) -> Self {
-        Self { done }
+    pub fn new(outcomes: mpsc::UnboundedSender<PipelineOutcome>) -> Self {
+        Self { outcomes }
     }
 }
 
@@ -28,27 +66,62 @@ impl Worker for CliCompletionSinkWorker {
             inputs: vec![
                 InputSpec {
                     event_type: ReportCompiled::EVENT_TYPE,
-                    queue_kind: QueueKind::Isolated { output_buffer: 4 },
+                    queue_kind: QueueKind::Isolated {
+                        output_buffer: STAGE_QUEUE_CAPACITY,
+                    },
+                },
+                InputSpec {
+                    event_type: BatchPlanned::EVENT_TYPE,
+                    queue_kind: QueueKind::Isolated {
+                        output_buffer: STAGE_QUEUE_CAPACITY,
+                    },
+                },
+                InputSpec {
+                    event_type: BatchCompleted::EVENT_TYPE,
+                    queue_kind: QueueKind::Isolated {
+                        output_buffer: STAGE_QUEUE_CAPACITY,
+                    },
                 },
                 InputSpec {
                     event_type: PipelineFailed::EVENT_TYPE,
-                    queue_kind: QueueKind::FifoDropOldest { capacity: 4 },
+                    queue_kind: QueueKind::FifoDropOldest {
+                        capacity: STAGE_QUEUE_CAPACITY,
+                    },
                 },
             ],
         }
     }
 
-    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
+    async fn handle(&mut self, event: Arc<EnrichedEvent>, _bus: &EventBus) -> anyhow::Result<()> {
+        // a closed receiver just means nobody is waiting any more
         if let Some(req) = downcast_ref::<ReportCompiled>(&event.event) {
-            if let Some(done) = self.done.take() {
-                done.send(Ok(req.report.clone()));
-            }
+            let _ = self.outcomes.send(PipelineOutcome::Job(Ok(CompletedJob {
+                job_id: req.job.job_id,
+                report: req.report.clone(),
+                report_path: req.report_path.clone(),
+                markdown_path: req.markdown_path.clone(),
+                usage: req.usage.clone(),
+                cache_hits: req.job.cache_hits.clone(),
+            })));
+        }
+
+        if let Some(req) = downcast_ref::<BatchPlanned>(&event.event) {
+            let _ = self.outcomes.send(PipelineOutcome::Planned {
+                job_id: req.job.job_id,
+                job_ids: req.plan.jobs.iter().map(|job| job.job_id).collect(),
+            });
+        }
+
+        if let Some(req) = downcast_ref::<BatchCompleted>(&event.event) {
+            let _ = self.outcomes.send(PipelineOutcome::Batch(CompletedBatch {
+                job_id: req.job.job_id,
+                index: req.index.clone(),
+                index_path: req.index_path.clone(),
+            }));
         }
 
         if let Some(req) = downcast_ref::<PipelineFailed>(&event.event) {
-            if let Some(done) = self.done.take() {
-                done.send(Err(req.clone()));
-            }
+            let _ = self.outcomes.send(PipelineOutcome::Job(Err(req.clone())));
         }
         Ok(())
     }
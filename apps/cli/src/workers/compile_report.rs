@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use bratishka_core::{
     events::{EnrichedEvent, EventBus, expect},
@@ -6,13 +6,21 @@ use bratishka_core::{
     workers::{InputSpec, SubscriptionSpec, Worker},
 };
 
+use tokio::fs;
+
 use crate::{
-    provider::{self, Provider},
+    cache::{get_report_markdown_path, get_report_path, get_usage_path},
+    llm::{LlmClient, LlmRequest, UsageTracker},
+    media::VideoMetadata,
+    timestamp::format_timestamp,
     types::{Transcript, VideoReport},
-    workers::events::{ReportCompiled, SectionsAnalyzed, SourceSection},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{ReportCompiled, SectionsAnalyzed, SourceSection},
+    },
 };
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct CompileReportWorker;
 
 impl CompileReportWorker {
@@ -21,15 +29,16 @@ impl CompileReportWorker {
     }
 
     async fn compile_report(
-        provider: &Provider,
+        client: &LlmClient,
         transcript: &Transcript,
         sections: &[SourceSection],
+        metadata: Option<&VideoMetadata>,
         report_lang: &str,
     ) -> anyhow::Result<VideoReport> {
-        let config = provider.config();
-        let api_key = provider.validate_api_key()?;
-
-        let duration_seconds = transcript.segments.last().map(|s| s.end).unwrap_or(0.0);
+        let duration_seconds = metadata
+            .and_then(|m| m.duration_seconds)
+            .or_else(|| transcript.segments.last().map(|s| s.end))
+            .unwrap_or(0.0);
         let duration_minutes = duration_seconds / 60.0;
 
         let system_prompt = format!(
@@ -74,6 +83,7 @@ impl CompileReportWorker {
   - Key takeaways = 5-7 actionable insights (what to DO, not just what was said)
   - Difficulty based on: concept density, abstraction level, prerequisite knowledge needed
   - Rewrite section summaries to be self-contained but connected
+  - Keep who-said-what from the section summaries when they attribute statements to speakers
   - Focus on making content easy to understand and retain
   - Output ONLY JSON, nothing else"#,
             lang = report_lang
@@ -81,49 +91,55 @@ impl CompileReportWorker {
 
         let prepared_sections = serde_json::to_string_pretty(&sections)?;
 
-        let user_prompt = format!(
+        let mut user_prompt = format!(
             "Analyze this video transcript (duration: {:.1} minutes, language: {}):\n\n{}",
             duration_minutes, transcript.language, prepared_sections
         );
+        if !transcript.non_speech.is_empty() {
+            let spans: Vec<String> = transcript
+                .non_speech
+                .iter()
+                .map(|s| {
+                    format!(
+                        "[{}–{}]",
+                        format_timestamp(s.start),
+                        format_timestamp(s.end)
+                    )
+                })
+                .collect();
+            user_prompt.push_str(&format!(
+                "\n\nStretches without speech (intro, music or breaks): {}",
+                spans.join(", ")
+            ));
+        }
+        if let Some(metadata) = metadata {
+            let context = metadata.prompt_context();
+            if !context.is_empty() {
+                user_prompt = format!("About the video:\n{context}\n\n{user_prompt}");
+            }
+            if let Some(title) = &metadata.title {
+                user_prompt.push_str(&format!(
+                    "\n\nUse the video's own title \"{title}\" as the report title, translated to {report_lang} if needed."
+                ));
+            }
+        }
 
-        let response = reqwest::Client::new()
-            .post(config.api_url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .json(&serde_json::json!({
-                "model": config.model,
-                        "tools": [{"type": "web_search"}],
-                "input": [
-                    {
-                        "role": "system",
-                        "content": &system_prompt,
-                    },
-                    {
-                        "role": "user",
-                        "content": user_prompt,
-                    },
-                ],
-                "temperature": 0.3,
-            }))
-            .send()
-            .await?
-            .json::<serde_json::Value>()
+        let report: VideoReport = client
+            .complete_structured(LlmRequest {
+                system: system_prompt,
+                user: user_prompt,
+                temperature: 0.3,
+                web_search: true,
+            })
             .await?;
 
-        // Extract content from response - /v1/responses format
-        let content = response["output"]
-            .as_array()
-            .and_then(|arr| arr.iter().rev().find(|item| item["type"] == "message"))
-            .and_then(|msg| msg["content"][0]["text"].as_str())
-            .ok_or_else(|| {
-                anyhow::anyhow!(format!("Invalid API response structure: {:?}", response))
-            })?;
-
-        // Parse JSON content into VideoReport
-        let report: VideoReport = serde_json::from_str(content)?;
-
         Ok(report)
     }
+
+    async fn save_json<T: serde::Serialize>(value: &T, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(value)?).await?;
+        Ok(())
+    }
 }
 
 impl Worker for CompileReportWorker {
@@ -134,7 +150,9 @@ impl Worker for CompileReportWorker {
             subscriber_id: Self::SUBSCRIBER_ID,
             inputs: vec![InputSpec {
                 event_type: SectionsAnalyzed::EVENT_TYPE,
-                queue_kind: QueueKind::FifoDropOldest { capacity: 4 },
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
             }],
         }
     }
@@ -147,15 +165,90 @@ impl Worker for CompileReportWorker {
             &req.transcript.language.clone()
         };
 
-        let report =
-            Self::compile_report(&req.job.provider, &req.transcript, &req.sections, &lang).await?;
+        // seed with the analysis stage's spend so --max-cost covers the whole job
+        let tracker = Arc::new(UsageTracker::new(req.usage.clone(), req.job.max_cost_usd));
+        let client = LlmClient::for_job(&req.job, Self::SUBSCRIBER_ID, Arc::clone(&tracker));
+
+        let report = Self::compile_report(
+            &client,
+            &req.transcript,
+            &req.sections,
+            req.job.metadata.as_ref(),
+            lang,
+        )
+        .await?;
+        let usage = tracker.usage();
+
+        let report_path = get_report_path(&req.job.cache_dir, &req.job.provider, lang);
+        Self::save_json(&report, &report_path).await?;
+        let markdown_path = get_report_markdown_path(&req.job.cache_dir, &req.job.provider, lang);
+        let markdown = report.to_markdown(req.job.metadata.as_ref(), req.job.source.url());
+        fs::write(&markdown_path, markdown).await?;
+        Self::save_json(
+            &usage,
+            &get_usage_path(&req.job.cache_dir, &req.job.provider, lang),
+        )
+        .await?;
 
         bus.publish(Arc::new(ReportCompiled::new(
             event.event.event_id(),
             req.job.clone(),
             report,
+            report_path,
+            markdown_path,
+            usage,
         )));
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        llm::JobUsage,
+        provider::Provider,
+        testing::{
+            CaptureBus, MockLlmServer, MockReply, REPORT_FIXTURE, TRANSCRIPT_FIXTURE,
+            transcribed_job,
+        },
+    };
+
+    #[tokio::test]
+    async fn uses_the_videos_own_title() {
+        let server = MockLlmServer::start().await;
+        server
+            .script("", vec![MockReply::content(REPORT_FIXTURE)])
+            .await;
+        let root = tempfile::tempdir().unwrap();
+        let mut job = transcribed_job(Provider::Grok, root.path(), &server.base_url());
+        job.metadata = Some(VideoMetadata {
+            title: Some("Ownership Explained".to_string()),
+            uploader: Some("Rustacean Station".to_string()),
+            ..Default::default()
+        });
+        let transcript: Transcript = serde_json::from_str(TRANSCRIPT_FIXTURE).unwrap();
+        let mut bus = CaptureBus::new(&[ReportCompiled::EVENT_TYPE]);
+
+        bus.deliver(
+            &mut CompileReportWorker::new(),
+            Arc::new(SectionsAnalyzed::new(
+                Uuid::new_v4(),
+                job,
+                Vec::new(),
+                transcript,
+                JobUsage::default(),
+            )),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(bus.take().len(), 1);
+        let prompt = server.requests().await[0].1.to_string();
+        assert!(prompt.contains("Channel: Rustacean Station"));
+        assert!(prompt.contains("video's own title \\\"Ownership Explained\\\""));
+    }
+}
@@ -0,0 +1,158 @@
+use std::{path::Path, sync::Arc};
+
+use bratishka_core::{
+    events::{EnrichedEvent, EventBus, expect},
+    queues::QueueKind,
+    workers::{InputSpec, SubscriptionSpec, Worker},
+};
+use tokio::fs;
+
+use crate::{
+    cache::{get_audio_path, get_speakers_path},
+    diarize::diarize,
+    types::Transcript,
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{AudioTranscribed, SpeakersAssigned},
+    },
+};
+
+/// Labels who is talking in each segment, for jobs run with `--diarize`
+#[derive(Clone, Default)]
+pub struct DiarizeSpeakersWorker;
+
+impl DiarizeSpeakersWorker {
+    /// A cached diarization only applies to the transcript it was made from
+    async fn load_diarized(path: &Path, transcript: &Transcript) -> Option<Transcript> {
+        let json_content = fs::read_to_string(path).await.ok()?;
+        let cached: Transcript = serde_json::from_str(&json_content).ok()?;
+        let same_segments = cached.segments.len() == transcript.segments.len()
+            && cached
+                .segments
+                .iter()
+                .zip(&transcript.segments)
+                .all(|(a, b)| a.start == b.start && a.end == b.end);
+        same_segments.then_some(cached)
+    }
+}
+
+impl Worker for DiarizeSpeakersWorker {
+    const SUBSCRIBER_ID: &'static str = "diarize.speakers";
+
+    fn subscription() -> SubscriptionSpec {
+        SubscriptionSpec {
+            subscriber_id: Self::SUBSCRIBER_ID,
+            inputs: vec![InputSpec {
+                event_type: AudioTranscribed::EVENT_TYPE,
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
+            }],
+        }
+    }
+
+    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
+        let req = expect::<AudioTranscribed>(&event.event, AudioTranscribed::EVENT_TYPE)?;
+        // without --diarize, sections are analyzed straight from AudioTranscribed
+        if !req.job.diarize {
+            return Ok(());
+        }
+
+        let job = &req.job;
+        let speakers_path = get_speakers_path(&job.cache_dir, &job.whisper, job.speakers);
+        let cached = if job.force {
+            None
+        } else {
+            Self::load_diarized(&speakers_path, &req.transcript).await
+        };
+
+        let (job, transcript) = match cached {
+            Some(transcript) => (job.with_cache_hit(Self::SUBSCRIBER_ID), transcript),
+            None => {
+                let audio_path = get_audio_path(&job.cache_dir);
+                let mut transcript = req.transcript.clone();
+                // transcripts from subtitles never needed the audio
+                if audio_path.is_file() {
+                    let segments = transcript.segments.clone();
+                    let speakers = job.speakers;
+                    let labels = tokio::task::spawn_blocking(move || {
+                        diarize(&audio_path, &segments, speakers)
+                    })
+                    .await??;
+                    for (segment, speaker) in transcript.segments.iter_mut().zip(labels) {
+                        segment.speaker = speaker;
+                    }
+                    fs::write(&speakers_path, serde_json::to_string_pretty(&transcript)?).await?;
+                } else {
+                    println!(
+                        "no audio to tell speakers apart in {}; use --transcript-source whisper to diarize",
+                        job.cache_dir.display()
+                    );
+                }
+                (job.clone(), transcript)
+            }
+        };
+
+        bus.publish(Arc::new(SpeakersAssigned::new(
+            event.event.event_id(),
+            job,
+            transcript,
+        )));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bratishka_core::events::downcast_ref;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        provider::Provider,
+        testing::{CaptureBus, TRANSCRIPT_FIXTURE, transcribed_job},
+        workers::events::JobSpec,
+    };
+
+    #[tokio::test]
+    async fn reuses_labels_of_the_same_transcript() {
+        let root = tempfile::tempdir().unwrap();
+        let job = JobSpec {
+            diarize: true,
+            ..transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9")
+        };
+        let transcript: Transcript = serde_json::from_str(TRANSCRIPT_FIXTURE).unwrap();
+        // labels from an earlier run on the same transcript
+        let mut labelled = transcript.clone();
+        for segment in &mut labelled.segments {
+            segment.speaker = Some("Speaker 2".to_string());
+        }
+        std::fs::write(
+            get_speakers_path(&job.cache_dir, &job.whisper, None),
+            serde_json::to_string(&labelled).unwrap(),
+        )
+        .unwrap();
+        let mut bus = CaptureBus::new(&[SpeakersAssigned::EVENT_TYPE]);
+
+        bus.deliver(
+            &mut DiarizeSpeakersWorker,
+            Arc::new(AudioTranscribed::new(Uuid::new_v4(), job, transcript)),
+        )
+        .await
+        .unwrap();
+
+        let published = bus.take();
+        let assigned = downcast_ref::<SpeakersAssigned>(&published[0]).unwrap();
+        assert_eq!(
+            assigned.job.cache_hits,
+            vec![DiarizeSpeakersWorker::SUBSCRIBER_ID]
+        );
+        assert!(
+            assigned
+                .transcript
+                .segments
+                .iter()
+                .all(|s| s.speaker.as_deref() == Some("Speaker 2"))
+        );
+    }
+}
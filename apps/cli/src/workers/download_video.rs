@@ -1,44 +1,34 @@
-use std::{
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use std::sync::Arc;
 
 use bratishka_core::{
     events::{EnrichedEvent, EventBus, expect},
     queues::QueueKind,
     workers::{InputSpec, SubscriptionSpec, Worker},
 };
-use tokio::process::Command;
 
-use crate::workers::events::{YoutubeUrlRequested, YoutubeVideoDownloaded};
+use crate::{
+    cache::{find_video_in_cache, is_audio_only},
+    media::{MediaFetcher, MediaKind, YtDlp},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{SubtitlesUnavailable, YoutubeVideoDownloaded},
+    },
+};
 
-#[derive(Default)]
-pub struct DownloadVideoWorker;
+#[derive(Clone)]
+pub struct DownloadVideoWorker {
+    fetcher: Arc<dyn MediaFetcher>,
+}
 
 impl DownloadVideoWorker {
-    pub async fn download_video(url: &str, cache_dir: &Path) -> anyhow::Result<PathBuf> {
-        let output_template = cache_dir.join("video.%(ext)s");
-        let output = Command::new("yt-dlp")
-            .arg(&url)
-            .arg("--print")
-            .arg("after_move:filepath")
-            .arg("--extractor-args")
-            .arg("youtube:player_client=android,web")
-            .arg("-f")
-            .arg("best")
-            .arg("-o")
-            .arg(&output_template)
-            .output()
-            .await?;
-
-        if !output.status.success() {
-            return Err(anyhow::anyhow!("{}", output.status));
-        }
-
-        let stdout_str = String::from_utf8_lossy(output.stdout.as_slice());
-        let filepath = stdout_str.trim();
+    pub fn new(fetcher: Arc<dyn MediaFetcher>) -> Self {
+        Self { fetcher }
+    }
+}
 
-        Ok(filepath.into())
+impl Default for DownloadVideoWorker {
+    fn default() -> Self {
+        Self::new(Arc::new(YtDlp))
     }
 }
 
@@ -49,22 +39,116 @@ impl Worker for DownloadVideoWorker {
         SubscriptionSpec {
             subscriber_id: Self::SUBSCRIBER_ID,
             inputs: vec![InputSpec {
-                event_type: YoutubeUrlRequested::EVENT_TYPE,
-                queue_kind: QueueKind::FifoDropOldest { capacity: 4 },
+                event_type: SubtitlesUnavailable::EVENT_TYPE,
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
             }],
         }
     }
 
     async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
-        let req = expect::<YoutubeUrlRequested>(&event.event, YoutubeUrlRequested::EVENT_TYPE)?;
-        let video_file_path = Self::download_video(&req.job.url, &req.job.cache_dir).await?;
+        let req = expect::<SubtitlesUnavailable>(&event.event, SubtitlesUnavailable::EVENT_TYPE)?;
+        let kind = if req.job.keep_video {
+            MediaKind::Video
+        } else {
+            MediaKind::AudioOnly
+        };
+        // an audio-only download can't satisfy a later --keep-video run
+        let cached = find_video_in_cache(&req.job.cache_dir).filter(|path| {
+            !req.job.force && (kind == MediaKind::AudioOnly || !is_audio_only(path))
+        });
+
+        let (job, video_file_path) = match cached {
+            Some(path) => (req.job.with_cache_hit(Self::SUBSCRIBER_ID), path),
+            None => {
+                let url = req
+                    .job
+                    .source
+                    .url()
+                    .ok_or_else(|| anyhow::anyhow!("job has no URL to download"))?;
+                let path = self
+                    .fetcher
+                    .fetch_media(url, &req.job.cache_dir, kind)
+                    .await?;
+                (req.job.clone(), path)
+            }
+        };
 
         bus.publish(Arc::new(YoutubeVideoDownloaded::new(
             event.event.event_id(),
-            req.job.clone(),
+            job,
             video_file_path,
         )));
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use bratishka_core::events::downcast_ref;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        provider::Provider,
+        testing::{CaptureBus, FakeMediaFetcher, transcribed_job},
+        workers::events::JobSpec,
+    };
+
+    /// The job and media path the worker passes on
+    async fn download(fetcher: &Arc<FakeMediaFetcher>, job: JobSpec) -> (JobSpec, PathBuf) {
+        let mut worker = DownloadVideoWorker::new(Arc::clone(fetcher) as Arc<dyn MediaFetcher>);
+        let mut bus = CaptureBus::new(&[YoutubeVideoDownloaded::EVENT_TYPE]);
+        bus.deliver(
+            &mut worker,
+            Arc::new(SubtitlesUnavailable::new(
+                Uuid::new_v4(),
+                job,
+                "no subtitles".to_string(),
+            )),
+        )
+        .await
+        .unwrap();
+
+        let published = bus.take();
+        let downloaded = downcast_ref::<YoutubeVideoDownloaded>(&published[0]).unwrap();
+        (downloaded.job.clone(), downloaded.video_file_path.clone())
+    }
+
+    #[tokio::test]
+    async fn reuses_cached_video() {
+        let root = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(FakeMediaFetcher::default());
+        let job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        std::fs::write(job.cache_dir.join("video.mp4"), b"").unwrap();
+
+        let (job, _) = download(&fetcher, job).await;
+
+        assert_eq!(fetcher.calls(), 0);
+        assert_eq!(job.cache_hits, vec![DownloadVideoWorker::SUBSCRIBER_ID]);
+    }
+
+    #[tokio::test]
+    async fn downloads_audio_only_unless_video_is_kept() {
+        let root = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(FakeMediaFetcher::default());
+        let job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+
+        let (_, path) = download(&fetcher, job.clone()).await;
+        assert!(path.ends_with("audio.m4a"));
+
+        // cached audio is not enough once the video is wanted
+        let job = JobSpec {
+            keep_video: true,
+            ..job
+        };
+        let (job, path) = download(&fetcher, job).await;
+        assert!(path.ends_with("video.mp4"));
+        assert!(job.cache_hits.is_empty());
+        assert_eq!(fetcher.calls(), 2);
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use bratishka_core::events::Event;
+
+use crate::workers::events::{EventHeader, JobSpec};
+
+/// Outcome of every job in a batch, persisted as the batch index
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchIndex {
+    pub batch_id: uuid::Uuid,
+    pub source_url: String,
+    pub title: Option<String>,
+    pub entries: Vec<BatchIndexEntry>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchIndexEntry {
+    pub url: String,
+    pub title: Option<String>,
+    pub upload_date: Option<String>,
+    /// Set when the job produced a report
+    pub report_path: Option<PathBuf>,
+    pub report_title: Option<String>,
+    pub cost_usd: f64,
+    /// `stage: message` of the failure, when the job failed
+    pub error: Option<String>,
+}
+
+impl BatchIndex {
+    pub fn completed(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.report_path.is_some())
+            .count()
+    }
+
+    pub fn total_cost_usd(&self) -> f64 {
+        self.entries.iter().map(|e| e.cost_usd).sum()
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct BatchCompleted {
+    pub header: EventHeader,
+    /// The playlist job that was expanded
+    pub job: JobSpec,
+    pub index: BatchIndex,
+    pub index_path: PathBuf,
+}
+
+impl BatchCompleted {
+    pub const EVENT_TYPE: &'static str = "batch.completed";
+
+    pub fn new(
+        parent_event_id: uuid::Uuid,
+        job: JobSpec,
+        index: BatchIndex,
+        index_path: PathBuf,
+    ) -> Self {
+        Self {
+            header: EventHeader {
+                event_id: uuid::Uuid::new_v4(),
+                parent_ids: vec![parent_event_id],
+                timestamp: std::time::SystemTime::now(),
+            },
+            job,
+            index,
+            index_path,
+        }
+    }
+}
+
+impl Event for BatchCompleted {
+    fn event_id(&self) -> uuid::Uuid {
+        self.header.event_id
+    }
+
+    fn parent_ids(&self) -> &[uuid::Uuid] {
+        &self.header.parent_ids
+    }
+
+    fn event_type(&self) -> &'static str {
+        Self::EVENT_TYPE
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.header.timestamp
+    }
+
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
@@ -0,0 +1,71 @@
+use bratishka_core::events::Event;
+
+use crate::workers::events::{EventHeader, JobSpec};
+
+/// The per-video jobs a playlist expanded into
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchPlan {
+    pub batch_id: uuid::Uuid,
+    pub source_url: String,
+    pub title: Option<String>,
+    pub jobs: Vec<PlannedJob>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlannedJob {
+    pub job_id: uuid::Uuid,
+    pub url: String,
+    pub title: Option<String>,
+    pub upload_date: Option<String>,
+}
+
+/// Published before any of the batch's jobs, so their outcomes can be collected
+#[derive(serde::Serialize)]
+pub struct BatchPlanned {
+    pub header: EventHeader,
+    /// The playlist job that was expanded
+    pub job: JobSpec,
+    pub plan: BatchPlan,
+}
+
+impl BatchPlanned {
+    pub const EVENT_TYPE: &'static str = "batch.planned";
+
+    pub fn new(parent_event_id: uuid::Uuid, job: JobSpec, plan: BatchPlan) -> Self {
+        Self {
+            header: EventHeader {
+                event_id: uuid::Uuid::new_v4(),
+                parent_ids: vec![parent_event_id],
+                timestamp: std::time::SystemTime::now(),
+            },
+            job,
+            plan,
+        }
+    }
+}
+
+impl Event for BatchPlanned {
+    fn event_id(&self) -> uuid::Uuid {
+        self.header.event_id
+    }
+
+    fn parent_ids(&self) -> &[uuid::Uuid] {
+        &self.header.parent_ids
+    }
+
+    fn event_type(&self) -> &'static str {
+        Self::EVENT_TYPE
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.header.timestamp
+    }
+
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
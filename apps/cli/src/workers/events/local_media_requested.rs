@@ -0,0 +1,53 @@
+use bratishka_core::events::Event;
+
+use crate::workers::events::{EventHeader, JobSpec};
+
+/// Entry event for a file on disk; it skips the download stage entirely
+#[derive(serde::Serialize)]
+pub struct LocalMediaRequested {
+    pub header: EventHeader,
+    pub job: JobSpec,
+    pub media_file_path: std::path::PathBuf,
+}
+
+impl LocalMediaRequested {
+    pub const EVENT_TYPE: &'static str = "local.media_requested";
+
+    pub fn new(job: JobSpec, media_file_path: std::path::PathBuf) -> Self {
+        Self {
+            header: EventHeader {
+                event_id: uuid::Uuid::new_v4(),
+                parent_ids: Vec::new(),
+                timestamp: std::time::SystemTime::now(),
+            },
+            job,
+            media_file_path,
+        }
+    }
+}
+
+impl Event for LocalMediaRequested {
+    fn event_id(&self) -> uuid::Uuid {
+        self.header.event_id
+    }
+
+    fn parent_ids(&self) -> &[uuid::Uuid] {
+        &self.header.parent_ids
+    }
+
+    fn event_type(&self) -> &'static str {
+        Self::EVENT_TYPE
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.header.timestamp
+    }
+
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
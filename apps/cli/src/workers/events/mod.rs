@@ -1,15 +1,29 @@
 pub mod audio_transcribed;
+pub mod batch_completed;
+pub mod batch_planned;
+pub mod local_media_requested;
+pub mod playlist_requested;
 pub mod report_compiled;
 pub mod sections_analyzed;
+pub mod speakers_assigned;
+pub mod subtitles_unavailable;
 pub mod youtube_audio_extracted;
+pub mod youtube_metadata_fetched;
 pub mod youtube_url_requested;
 pub mod youtube_video_downloaded;
 
 pub use audio_transcribed::*;
+pub use batch_completed::*;
+pub use batch_planned::*;
+pub use local_media_requested::*;
+pub use playlist_requested::*;
 pub use report_compiled::*;
 pub use sections_analyzed::*;
+pub use speakers_assigned::*;
 use std::time::SystemTime;
+pub use subtitles_unavailable::*;
 pub use youtube_audio_extracted::*;
+pub use youtube_metadata_fetched::*;
 pub use youtube_url_requested::*;
 pub use youtube_video_downloaded::*;
 
@@ -0,0 +1,51 @@
+use bratishka_core::events::Event;
+
+use crate::workers::events::{EventHeader, JobSpec};
+
+/// Entry event for a playlist or channel URL, expanded into one job per video
+#[derive(serde::Serialize)]
+pub struct PlaylistRequested {
+    pub header: EventHeader,
+    pub job: JobSpec,
+}
+
+impl PlaylistRequested {
+    pub const EVENT_TYPE: &'static str = "youtube.playlist_requested";
+
+    pub fn new(job: JobSpec) -> Self {
+        Self {
+            header: EventHeader {
+                event_id: uuid::Uuid::new_v4(),
+                parent_ids: Vec::new(),
+                timestamp: std::time::SystemTime::now(),
+            },
+            job,
+        }
+    }
+}
+
+impl Event for PlaylistRequested {
+    fn event_id(&self) -> uuid::Uuid {
+        self.header.event_id
+    }
+
+    fn parent_ids(&self) -> &[uuid::Uuid] {
+        &self.header.parent_ids
+    }
+
+    fn event_type(&self) -> &'static str {
+        Self::EVENT_TYPE
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.header.timestamp
+    }
+
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
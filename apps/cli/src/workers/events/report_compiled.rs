@@ -1,6 +1,9 @@
 use bratishka_core::events::Event;
 
+use std::path::PathBuf;
+
 use crate::{
+    llm::JobUsage,
     types::VideoReport,
     workers::events::{EventHeader, JobSpec},
 };
@@ -10,12 +13,23 @@ pub struct ReportCompiled {
     pub header: EventHeader,
     pub job: JobSpec,
     pub report: VideoReport,
+    pub report_path: PathBuf,
+    /// The same report rendered as markdown
+    pub markdown_path: PathBuf,
+    pub usage: JobUsage,
 }
 
 impl ReportCompiled {
     pub const EVENT_TYPE: &'static str = "report.compiled";
 
-    pub fn new(parent_event_id: uuid::Uuid, job: JobSpec, report: VideoReport) -> Self {
+    pub fn new(
+        parent_event_id: uuid::Uuid,
+        job: JobSpec,
+        report: VideoReport,
+        report_path: PathBuf,
+        markdown_path: PathBuf,
+        usage: JobUsage,
+    ) -> Self {
         Self {
             header: EventHeader {
                 event_id: uuid::Uuid::new_v4(),
@@ -24,6 +38,9 @@ impl ReportCompiled {
             },
             job,
             report,
+            report_path,
+            markdown_path,
+            usage,
         }
     }
 }
@@ -45,6 +62,10 @@ impl Event for ReportCompiled {
         self.header.timestamp
     }
 
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self as &dyn std::any::Any
     }
@@ -1,6 +1,7 @@
 use bratishka_core::events::Event;
 
 use crate::{
+    llm::{JobUsage, JsonSchema},
     types::Transcript,
     workers::events::{EventHeader, JobSpec},
 };
@@ -16,12 +17,37 @@ pub struct SourceSection {
     pub summary: String,
 }
 
+impl JsonSchema for SourceSection {
+    const SCHEMA_NAME: &'static str = "source_section";
+
+    fn json_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "content": { "type": "string" },
+                "started_at": { "type": "number" },
+                "ended_at": { "type": "number" },
+                "key_concepts": { "type": "array", "items": { "type": "string" } },
+                "external_context": { "type": "string" },
+                "summary": { "type": "string" }
+            },
+            "required": [
+                "name", "content", "started_at", "ended_at",
+                "key_concepts", "external_context", "summary"
+            ],
+            "additionalProperties": false
+        })
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct SectionsAnalyzed {
     pub header: EventHeader,
     pub job: JobSpec,
     pub sections: Vec<SourceSection>,
     pub transcript: Transcript,
+    pub usage: JobUsage,
 }
 
 impl SectionsAnalyzed {
@@ -32,6 +58,7 @@ impl SectionsAnalyzed {
         job: JobSpec,
         sections: Vec<SourceSection>,
         transcript: Transcript,
+        usage: JobUsage,
     ) -> Self {
         Self {
             header: EventHeader {
@@ -42,6 +69,7 @@ impl SectionsAnalyzed {
             job,
             sections,
             transcript,
+            usage,
         }
     }
 }
@@ -63,6 +91,10 @@ impl Event for SectionsAnalyzed {
         self.header.timestamp
     }
 
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self as &dyn std::any::Any
     }
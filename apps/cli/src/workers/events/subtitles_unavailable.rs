@@ -0,0 +1,53 @@
+use bratishka_core::events::Event;
+
+use crate::workers::events::{EventHeader, JobSpec};
+
+#[derive(serde::Serialize)]
+pub struct SubtitlesUnavailable {
+    pub header: EventHeader,
+    pub job: JobSpec,
+    /// Why whisper has to transcribe this job
+    pub reason: String,
+}
+
+impl SubtitlesUnavailable {
+    pub const EVENT_TYPE: &'static str = "youtube.subtitles_unavailable";
+
+    pub fn new(parent_event_id: uuid::Uuid, job: JobSpec, reason: String) -> Self {
+        Self {
+            header: EventHeader {
+                event_id: uuid::Uuid::new_v4(),
+                parent_ids: vec![parent_event_id],
+                timestamp: std::time::SystemTime::now(),
+            },
+            job,
+            reason,
+        }
+    }
+}
+
+impl Event for SubtitlesUnavailable {
+    fn event_id(&self) -> uuid::Uuid {
+        self.header.event_id
+    }
+
+    fn parent_ids(&self) -> &[uuid::Uuid] {
+        &self.header.parent_ids
+    }
+
+    fn event_type(&self) -> &'static str {
+        Self::EVENT_TYPE
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.header.timestamp
+    }
+
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
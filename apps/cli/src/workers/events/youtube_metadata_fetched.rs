@@ -0,0 +1,51 @@
+use bratishka_core::events::Event;
+
+use crate::workers::events::{EventHeader, JobSpec};
+
+#[derive(serde::Serialize)]
+pub struct YoutubeMetadataFetched {
+    pub header: EventHeader,
+    /// Carries the metadata in `job.metadata`, unless the lookup failed
+    pub job: JobSpec,
+}
+
+impl YoutubeMetadataFetched {
+    pub const EVENT_TYPE: &'static str = "youtube.metadata_fetched";
+
+    pub fn new(parent_event_id: uuid::Uuid, job: JobSpec) -> Self {
+        Self {
+            header: EventHeader {
+                event_id: uuid::Uuid::new_v4(),
+                parent_ids: vec![parent_event_id],
+                timestamp: std::time::SystemTime::now(),
+            },
+            job,
+        }
+    }
+}
+
+impl Event for YoutubeMetadataFetched {
+    fn event_id(&self) -> uuid::Uuid {
+        self.header.event_id
+    }
+
+    fn parent_ids(&self) -> &[uuid::Uuid] {
+        &self.header.parent_ids
+    }
+
+    fn event_type(&self) -> &'static str {
+        Self::EVENT_TYPE
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.header.timestamp
+    }
+
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
@@ -1,42 +1,176 @@
-use std::{path::PathBuf, time::SystemTime};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 use bratishka_core::events::Event;
 use uuid::Uuid;
 
-use crate::{pipeline_old::ensure_model, provider::Provider, workers::events::EventHeader};
+use crate::{
+    media::{PlaylistFilter, TranscriptSource, VideoMetadata, collection_url},
+    provider::Provider,
+    whisper::WhisperOptions,
+    workers::events::{EventHeader, LocalMediaRequested, PlaylistRequested},
+};
+
+/// Where a job's media comes from
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub enum MediaSource {
+    Url(String),
+    LocalFile(PathBuf),
+}
+
+impl MediaSource {
+    /// Treat `input` as a local file when one exists at that path, otherwise as a URL
+    pub fn detect(input: &str) -> anyhow::Result<Self> {
+        let path = Path::new(input);
+        if path.is_file() {
+            return Ok(Self::LocalFile(path.canonicalize()?));
+        }
+        if input.starts_with("http://") || input.starts_with("https://") {
+            return Ok(Self::Url(input.to_string()));
+        }
+        Err(anyhow::anyhow!(
+            "{input} is neither an existing file nor an http(s) URL"
+        ))
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::Url(url) => Some(url),
+            Self::LocalFile(_) => None,
+        }
+    }
+}
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct JobSpec {
-    pub url: String,
+    /// Correlates every event of this job, see [`Event::correlation_id`]
+    pub job_id: Uuid,
+    /// Set on jobs expanded from a playlist or channel
+    pub batch_id: Option<Uuid>,
+    pub source: MediaSource,
     pub force: bool,
+    /// Download the full video instead of just the audio stream
+    pub keep_video: bool,
+    /// Whether native subtitles, whisper, or either may produce the transcript
+    #[serde(default)]
+    pub transcript_source: TranscriptSource,
+    /// How whisper decodes the audio, when it runs
+    #[serde(default)]
+    pub whisper: WhisperOptions,
+    /// Label segments with who is speaking before the analysis
+    #[serde(default)]
+    pub diarize: bool,
+    /// Known number of speakers; guessed when `None`
+    #[serde(default)]
+    pub speakers: Option<usize>,
     pub provider: Provider,
     pub requested_report_lang: Option<String>,
+    /// Abort before an LLM call would push the job's estimated spend past this (USD)
+    pub max_cost_usd: Option<f64>,
+    /// Reuse identical LLM requests from the shared response cache
+    pub llm_cache: bool,
+    /// Send LLM requests to this host instead of the provider's public API
+    pub api_base_url: Option<String>,
+    /// Let LLM requests go out without an API key, for the mock server in tests
+    #[cfg(test)]
+    #[serde(skip)]
+    pub without_api_key: bool,
+    /// Which videos of a playlist or channel to process
+    pub playlist_filter: PlaylistFilter,
+    /// What yt-dlp reported about the video, once looked up
+    #[serde(default)]
+    pub metadata: Option<VideoMetadata>,
+    /// Stages that reused a cached artifact instead of doing the work
+    #[serde(default)]
+    pub cache_hits: Vec<String>,
 
     // pure derived values
     pub root_cache_dir: PathBuf,
     pub cache_dir: PathBuf,
-    pub model_path: PathBuf,
+    /// Whisper model to transcribe with, installed once a job needs it
+    pub model: String,
 }
 
 impl JobSpec {
-    pub async fn from_cli(cli: crate::Cli) -> anyhow::Result<Self> {
-        let provider: Provider = cli.provider.into();
+    pub fn from_args(input: &str, args: &crate::JobArgs) -> anyhow::Result<Self> {
+        let provider: Provider = args.provider.clone().into();
+
+        let source = MediaSource::detect(input)?;
 
         let root_cache_dir = crate::cache::get_root_cache_dir();
-        let cache_dir = crate::cache::get_cache_dir(&cli.url);
+        let cache_dir = match &source {
+            MediaSource::Url(url) => crate::cache::get_cache_dir(&root_cache_dir, url),
+            MediaSource::LocalFile(path) => {
+                crate::cache::get_file_cache_dir(&root_cache_dir, path)?
+            }
+        };
         std::fs::create_dir_all(&cache_dir)?;
-        let model_path = ensure_model(&root_cache_dir).await?;
 
         Ok(Self {
-            url: cli.url,
-            force: cli.force,
+            job_id: Uuid::new_v4(),
+            batch_id: None,
+            source,
+            force: args.force,
+            keep_video: args.keep_video,
+            transcript_source: args.transcript_source.into(),
+            whisper: WhisperOptions {
+                language: args.source_lang.clone(),
+                translate: args.translate,
+                beam_size: args.beam_size,
+                temperature_inc: args.temperature_inc,
+                initial_prompt: args.initial_prompt.clone(),
+                threads: args.threads,
+                device: args.device.map(Into::into),
+                vad: !args.no_vad,
+                filter: !args.no_filter,
+            },
+            diarize: args.diarize || args.speakers.is_some(),
+            speakers: args.speakers.map(usize::from),
             provider,
-            requested_report_lang: cli.lang,
+            requested_report_lang: args.lang.clone(),
+            max_cost_usd: args.max_cost,
+            llm_cache: !args.no_llm_cache,
+            api_base_url: args.api_base_url.clone(),
+            #[cfg(test)]
+            without_api_key: false,
+            playlist_filter: PlaylistFilter {
+                limit: args.limit,
+                since: args.since.clone(),
+                until: args.until.clone(),
+            },
+            metadata: None,
+            cache_hits: Vec::new(),
             root_cache_dir,
             cache_dir,
-            model_path,
+            model: args.model.clone(),
         })
     }
+
+    /// This job as passed on by a stage that was served from the cache
+    pub fn with_cache_hit(&self, stage: &str) -> Self {
+        let mut job = self.clone();
+        job.cache_hits.push(stage.to_string());
+        job
+    }
+
+    /// Whether this job expands into a batch of per-video jobs
+    pub fn is_batch(&self) -> bool {
+        self.source.url().and_then(collection_url).is_some()
+    }
+
+    /// The event that starts this job, depending on where its media comes from
+    pub fn into_request(self) -> Arc<dyn Event> {
+        match self.source.clone() {
+            MediaSource::Url(url) if collection_url(&url).is_some() => {
+                Arc::new(PlaylistRequested::new(self))
+            }
+            MediaSource::Url(_) => Arc::new(YoutubeUrlRequested::new(self)),
+            MediaSource::LocalFile(path) => Arc::new(LocalMediaRequested::new(self, path)),
+        }
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -59,6 +193,13 @@ impl YoutubeUrlRequested {
             job,
         }
     }
+
+    /// A job planned by an earlier stage, e.g. one video of a playlist
+    pub fn with_parent(parent_event_id: Uuid, job: JobSpec) -> Self {
+        let mut event = Self::new(job);
+        event.header.parent_ids.push(parent_event_id);
+        event
+    }
 }
 
 impl Event for YoutubeUrlRequested {
@@ -78,7 +219,47 @@ impl Event for YoutubeUrlRequested {
         self.header.timestamp
     }
 
+    fn correlation_id(&self) -> Option<Uuid> {
+        Some(self.job.job_id)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self as &dyn std::any::Any
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::transcribed_job;
+
+    #[test]
+    fn sources_pick_their_first_stage() {
+        let root = tempfile::tempdir().unwrap();
+        let recording = root.path().join("meeting.mkv");
+        std::fs::write(&recording, b"screen capture").unwrap();
+        let job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        let request = |input: &str| {
+            JobSpec {
+                source: MediaSource::detect(input).unwrap(),
+                ..job.clone()
+            }
+            .into_request()
+            .event_type()
+        };
+
+        // local files skip the download
+        assert_eq!(
+            request(recording.to_str().unwrap()),
+            LocalMediaRequested::EVENT_TYPE
+        );
+        assert_eq!(
+            request("https://www.youtube.com/watch?v=offline"),
+            YoutubeUrlRequested::EVENT_TYPE
+        );
+        assert_eq!(
+            request("https://www.youtube.com/playlist?list=PLconf"),
+            PlaylistRequested::EVENT_TYPE
+        );
+    }
+}
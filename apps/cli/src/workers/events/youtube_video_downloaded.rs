@@ -46,6 +46,10 @@ impl Event for YoutubeVideoDownloaded {
         self.header.timestamp
     }
 
+    fn correlation_id(&self) -> Option<uuid::Uuid> {
+        Some(self.job.job_id)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self as &dyn std::any::Any
     }
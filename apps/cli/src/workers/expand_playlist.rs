@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use bratishka_core::{
+    events::{EnrichedEvent, EventBus, expect},
+    queues::QueueKind,
+    workers::{InputSpec, SubscriptionSpec, Worker},
+};
+use uuid::Uuid;
+
+use crate::{
+    cache::get_cache_dir,
+    media::{MediaFetcher, YtDlp, collection_url},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        batch_index::BatchJobs,
+        events::{
+            BatchPlan, BatchPlanned, JobSpec, MediaSource, PlannedJob, PlaylistRequested,
+            YoutubeUrlRequested,
+        },
+    },
+};
+
+/// Turns a playlist or channel into one job per video, sharing a batch id
+pub struct ExpandPlaylistWorker {
+    fetcher: Arc<dyn MediaFetcher>,
+    batch_jobs: BatchJobs,
+}
+
+impl ExpandPlaylistWorker {
+    pub fn new(fetcher: Arc<dyn MediaFetcher>, batch_jobs: BatchJobs) -> Self {
+        Self {
+            fetcher,
+            batch_jobs,
+        }
+    }
+}
+
+impl Default for ExpandPlaylistWorker {
+    fn default() -> Self {
+        Self::new(Arc::new(YtDlp), BatchJobs::default())
+    }
+}
+
+impl Worker for ExpandPlaylistWorker {
+    const SUBSCRIBER_ID: &'static str = "youtube.expand_playlist";
+
+    fn subscription() -> SubscriptionSpec {
+        SubscriptionSpec {
+            subscriber_id: Self::SUBSCRIBER_ID,
+            inputs: vec![InputSpec {
+                event_type: PlaylistRequested::EVENT_TYPE,
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
+            }],
+        }
+    }
+
+    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
+        let req = expect::<PlaylistRequested>(&event.event, PlaylistRequested::EVENT_TYPE)?;
+        let source_url = req
+            .job
+            .source
+            .url()
+            .and_then(collection_url)
+            .ok_or_else(|| anyhow::anyhow!("job is not a playlist or channel URL"))?;
+
+        let playlist = self.fetcher.list_playlist(&source_url).await?;
+        let entries = req.job.playlist_filter.apply(playlist.entries);
+        let batch_id = Uuid::new_v4();
+
+        let mut jobs = Vec::with_capacity(entries.len());
+        let mut plan = BatchPlan {
+            batch_id,
+            source_url,
+            title: playlist.title,
+            jobs: Vec::with_capacity(entries.len()),
+        };
+        for entry in entries {
+            let cache_dir = get_cache_dir(&req.job.root_cache_dir, &entry.url);
+            std::fs::create_dir_all(&cache_dir)?;
+
+            let job = JobSpec {
+                job_id: Uuid::new_v4(),
+                batch_id: Some(batch_id),
+                source: MediaSource::Url(entry.url.clone()),
+                cache_dir,
+                ..req.job.clone()
+            };
+            plan.jobs.push(PlannedJob {
+                job_id: job.job_id,
+                url: entry.url,
+                title: entry.title,
+                upload_date: entry.upload_date,
+            });
+            jobs.push(job);
+        }
+
+        // the plan goes first so the index knows every job before any of them finishes
+        self.batch_jobs.mark(plan.jobs.iter().map(|j| j.job_id));
+        let planned = Arc::new(BatchPlanned::new(
+            event.event.event_id(),
+            req.job.clone(),
+            plan,
+        ));
+        let planned_id = planned.header.event_id;
+        bus.publish(planned);
+
+        // jobs go in as earlier ones finish, so the stage queues never overflow
+        for job in jobs {
+            self.batch_jobs.start(job.job_id).await;
+            bus.publish(Arc::new(YoutubeUrlRequested::with_parent(planned_id, job)));
+        }
+
+        Ok(())
+    }
+}
@@ -4,40 +4,27 @@ use std::{
 };
 
 use bratishka_core::{
-    events::{EnrichedEvent, Event, EventBus, expect},
+    events::{EnrichedEvent, EventBus, downcast_ref, expect},
     queues::QueueKind,
     workers::{InputSpec, PipelineFailed, SubscriptionSpec, Worker},
 };
-use tokio::process::Command;
 
-use crate::workers::events::{YoutubeAudioExtracted, YoutubeVideoDownloaded};
+use crate::{
+    media::{AudioExtractor, Ffmpeg},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{LocalMediaRequested, YoutubeAudioExtracted, YoutubeVideoDownloaded},
+    },
+};
 
-#[derive(Default)]
-pub struct ExtractAudioWorker;
+#[derive(Clone)]
+pub struct ExtractAudioWorker {
+    extractor: Arc<dyn AudioExtractor>,
+}
 
 impl ExtractAudioWorker {
-    pub fn new() -> Self {
-        Self
-    }
-
-    async fn extract_audio(video_path: &Path, audio_path: &Path) -> anyhow::Result<()> {
-        let output = Command::new("ffmpeg")
-            .arg("-y")
-            .arg("-i")
-            .arg(video_path)
-            .arg("-ar")
-            .arg("16000")
-            .arg("-ac")
-            .arg("1")
-            .arg(audio_path)
-            .output()
-            .await?;
-
-        if !output.status.success() {
-            return Err(anyhow::anyhow!("Failed to extract audio from video file"));
-        }
-
-        Ok(())
+    pub fn new(extractor: Arc<dyn AudioExtractor>) -> Self {
+        Self { extractor }
     }
 
     fn get_audio_path(cache_dir: &Path) -> PathBuf {
@@ -45,27 +32,50 @@ impl ExtractAudioWorker {
     }
 }
 
+impl Default for ExtractAudioWorker {
+    fn default() -> Self {
+        Self::new(Arc::new(Ffmpeg))
+    }
+}
+
 impl Worker for ExtractAudioWorker {
     const SUBSCRIBER_ID: &'static str = "youtube.extract_audio";
 
     fn subscription() -> SubscriptionSpec {
         SubscriptionSpec {
             subscriber_id: Self::SUBSCRIBER_ID,
-            inputs: vec![InputSpec {
-                event_type: YoutubeVideoDownloaded::EVENT_TYPE,
-                queue_kind: QueueKind::FifoDropOldest { capacity: 4 },
-            }],
+            inputs: vec![
+                InputSpec {
+                    event_type: YoutubeVideoDownloaded::EVENT_TYPE,
+                    queue_kind: QueueKind::FifoDropOldest {
+                        capacity: STAGE_QUEUE_CAPACITY,
+                    },
+                },
+                InputSpec {
+                    event_type: LocalMediaRequested::EVENT_TYPE,
+                    queue_kind: QueueKind::FifoDropOldest {
+                        capacity: STAGE_QUEUE_CAPACITY,
+                    },
+                },
+            ],
         }
     }
 
     async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
-        let req =
-            expect::<YoutubeVideoDownloaded>(&event.event, YoutubeVideoDownloaded::EVENT_TYPE)?;
+        // downloaded videos and local files only differ in where the media path lives
+        let (job, media_path) = if let Some(req) =
+            downcast_ref::<YoutubeVideoDownloaded>(&event.event)
+        {
+            (&req.job, &req.video_file_path)
+        } else {
+            let req = expect::<LocalMediaRequested>(&event.event, LocalMediaRequested::EVENT_TYPE)?;
+            (&req.job, &req.media_file_path)
+        };
 
-        let audio_path = Self::get_audio_path(&req.job.cache_dir);
-        let cached = !req.job.force && audio_path.exists();
+        let audio_path = Self::get_audio_path(&job.cache_dir);
+        let cached = !job.force && audio_path.exists();
 
-        if !cached && let Err(e) = Self::extract_audio(&req.video_file_path, &audio_path).await {
+        if !cached && let Err(e) = self.extractor.extract_audio(media_path, &audio_path).await {
             bus.publish(Arc::new(PipelineFailed::new(
                 Arc::clone(&event.event),
                 Self::SUBSCRIBER_ID,
@@ -75,11 +85,53 @@ impl Worker for ExtractAudioWorker {
             return Ok(());
         }
 
+        let job = if cached {
+            job.with_cache_hit(Self::SUBSCRIBER_ID)
+        } else {
+            job.clone()
+        };
         bus.publish(Arc::new(YoutubeAudioExtracted::new(
-            req.event_id(),
-            req.job.clone(),
+            event.event.event_id(),
+            job,
             audio_path,
         )));
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        provider::Provider,
+        testing::{CaptureBus, FakeAudioExtractor, transcribed_job},
+    };
+
+    #[tokio::test]
+    async fn reuses_cached_audio() {
+        let root = tempfile::tempdir().unwrap();
+        let job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        std::fs::write(ExtractAudioWorker::get_audio_path(&job.cache_dir), b"").unwrap();
+        let extractor = Arc::new(FakeAudioExtractor::default());
+        let mut worker = ExtractAudioWorker::new(Arc::clone(&extractor) as Arc<dyn AudioExtractor>);
+        let mut bus = CaptureBus::new(&[YoutubeAudioExtracted::EVENT_TYPE]);
+
+        let video_path = job.cache_dir.join("video.mp4");
+        bus.deliver(
+            &mut worker,
+            Arc::new(YoutubeVideoDownloaded::new(Uuid::new_v4(), job, video_path)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(extractor.calls(), 0);
+        let published = bus.take();
+        let extracted = downcast_ref::<YoutubeAudioExtracted>(&published[0]).unwrap();
+        assert_eq!(
+            extracted.job.cache_hits,
+            vec![ExtractAudioWorker::SUBSCRIBER_ID]
+        );
+    }
+}
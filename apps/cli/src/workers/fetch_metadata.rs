@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use bratishka_core::{
+    events::{EnrichedEvent, EventBus, expect},
+    queues::QueueKind,
+    workers::{InputSpec, SubscriptionSpec, Worker},
+};
+use tokio::fs;
+
+use crate::{
+    cache::get_metadata_path,
+    media::{MediaFetcher, VideoMetadata, YtDlp},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{YoutubeMetadataFetched, YoutubeUrlRequested},
+    },
+};
+
+/// Looks up title, chapters and available subtitles before anything is downloaded
+#[derive(Clone)]
+pub struct FetchMetadataWorker {
+    fetcher: Arc<dyn MediaFetcher>,
+}
+
+impl FetchMetadataWorker {
+    pub fn new(fetcher: Arc<dyn MediaFetcher>) -> Self {
+        Self { fetcher }
+    }
+
+    async fn load_metadata(path: &std::path::Path) -> anyhow::Result<VideoMetadata> {
+        let json_content = fs::read_to_string(path).await?;
+        Ok(serde_json::from_str(&json_content)?)
+    }
+}
+
+impl Default for FetchMetadataWorker {
+    fn default() -> Self {
+        Self::new(Arc::new(YtDlp))
+    }
+}
+
+impl Worker for FetchMetadataWorker {
+    const SUBSCRIBER_ID: &'static str = "youtube.fetch_metadata";
+
+    fn subscription() -> SubscriptionSpec {
+        SubscriptionSpec {
+            subscriber_id: Self::SUBSCRIBER_ID,
+            inputs: vec![InputSpec {
+                event_type: YoutubeUrlRequested::EVENT_TYPE,
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
+            }],
+        }
+    }
+
+    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
+        let req = expect::<YoutubeUrlRequested>(&event.event, YoutubeUrlRequested::EVENT_TYPE)?;
+        let metadata_path = get_metadata_path(&req.job.cache_dir);
+
+        let cached = if req.job.force {
+            None
+        } else {
+            Self::load_metadata(&metadata_path).await.ok()
+        };
+        let (mut job, metadata) = match cached {
+            Some(metadata) => (req.job.with_cache_hit(Self::SUBSCRIBER_ID), Some(metadata)),
+            None => {
+                let url = req
+                    .job
+                    .source
+                    .url()
+                    .ok_or_else(|| anyhow::anyhow!("job has no URL to look up"))?;
+                // metadata only enriches the report, so a failed lookup is not
+                // fatal; the download reports the real problem if there is one
+                match self.fetcher.fetch_metadata(url).await {
+                    Ok(metadata) => {
+                        fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?).await?;
+                        (req.job.clone(), Some(metadata))
+                    }
+                    Err(e) => {
+                        println!(
+                            "could not look up the video metadata ({e}); continuing without it"
+                        );
+                        (req.job.clone(), None)
+                    }
+                }
+            }
+        };
+        job.metadata = metadata;
+
+        bus.publish(Arc::new(YoutubeMetadataFetched::new(
+            event.event.event_id(),
+            job,
+        )));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bratishka_core::events::downcast_ref;
+
+    use super::*;
+    use crate::{
+        provider::Provider,
+        testing::{CaptureBus, FakeMediaFetcher, transcribed_job},
+    };
+
+    #[tokio::test]
+    async fn saves_metadata_and_reuses_it() {
+        let root = tempfile::tempdir().unwrap();
+        let job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        let mut bus = CaptureBus::new(&[YoutubeMetadataFetched::EVENT_TYPE]);
+
+        let fetcher = FakeMediaFetcher::with_metadata(VideoMetadata {
+            title: Some("Ownership Explained".to_string()),
+            ..Default::default()
+        });
+        let mut worker = FetchMetadataWorker::new(Arc::new(fetcher));
+        bus.deliver(&mut worker, Arc::new(YoutubeUrlRequested::new(job.clone())))
+            .await
+            .unwrap();
+        let saved = std::fs::read_to_string(get_metadata_path(&job.cache_dir)).unwrap();
+        assert!(saved.contains("Ownership Explained"));
+
+        // a later run finds it in the cache, even if yt-dlp is down by then
+        let fetcher = FakeMediaFetcher::failing("offline");
+        let mut worker = FetchMetadataWorker::new(Arc::new(fetcher));
+        bus.deliver(&mut worker, Arc::new(YoutubeUrlRequested::new(job)))
+            .await
+            .unwrap();
+
+        let published = bus.take();
+        let fetched = downcast_ref::<YoutubeMetadataFetched>(&published[1]).unwrap();
+        let title = fetched
+            .job
+            .metadata
+            .as_ref()
+            .and_then(|m| m.title.as_deref());
+        assert_eq!(title, Some("Ownership Explained"));
+        assert_eq!(
+            fetched.job.cache_hits,
+            vec![FetchMetadataWorker::SUBSCRIBER_ID]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use bratishka_core::{
+    events::{EnrichedEvent, EventBus, expect},
+    queues::QueueKind,
+    workers::{InputSpec, SubscriptionSpec, Worker},
+};
+
+use crate::{
+    cache::find_subtitles_in_cache,
+    media::{MediaFetcher, TranscriptSource, YtDlp, load_subtitles},
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{AudioTranscribed, SubtitlesUnavailable, YoutubeMetadataFetched},
+    },
+};
+
+/// Turns a video's own subtitles into its transcript, so it never has to be
+/// downloaded or run through whisper
+#[derive(Clone)]
+pub struct FetchSubtitlesWorker {
+    fetcher: Arc<dyn MediaFetcher>,
+}
+
+impl FetchSubtitlesWorker {
+    pub fn new(fetcher: Arc<dyn MediaFetcher>) -> Self {
+        Self { fetcher }
+    }
+}
+
+impl Default for FetchSubtitlesWorker {
+    fn default() -> Self {
+        Self::new(Arc::new(YtDlp))
+    }
+}
+
+impl Worker for FetchSubtitlesWorker {
+    const SUBSCRIBER_ID: &'static str = "youtube.fetch_subtitles";
+
+    fn subscription() -> SubscriptionSpec {
+        SubscriptionSpec {
+            subscriber_id: Self::SUBSCRIBER_ID,
+            inputs: vec![InputSpec {
+                event_type: YoutubeMetadataFetched::EVENT_TYPE,
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
+            }],
+        }
+    }
+
+    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
+        let req =
+            expect::<YoutubeMetadataFetched>(&event.event, YoutubeMetadataFetched::EVENT_TYPE)?;
+        let source = req.job.transcript_source;
+
+        let fallback = |reason: String| -> anyhow::Result<()> {
+            match source {
+                TranscriptSource::Subs => {
+                    anyhow::bail!("{reason}, and --transcript-source subs rules out whisper")
+                }
+                TranscriptSource::Auto => println!("{reason}; transcribing with whisper"),
+                TranscriptSource::Whisper => {}
+            }
+            bus.publish(Arc::new(SubtitlesUnavailable::new(
+                event.event.event_id(),
+                req.job.clone(),
+                reason,
+            )));
+            Ok(())
+        };
+
+        if source == TranscriptSource::Whisper {
+            return fallback("whisper requested".to_string());
+        }
+
+        let cached = find_subtitles_in_cache(&req.job.cache_dir).filter(|_| !req.job.force);
+        let (job, path) = match cached {
+            Some(path) => (req.job.with_cache_hit(Self::SUBSCRIBER_ID), path),
+            None => {
+                let url = req
+                    .job
+                    .source
+                    .url()
+                    .ok_or_else(|| anyhow::anyhow!("job has no URL to fetch subtitles for"))?;
+                let Some(lang) = req
+                    .job
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.subtitle_lang.as_deref())
+                else {
+                    return fallback("no subtitles in the spoken language".to_string());
+                };
+                // auto mode treats a failed fetch like missing subtitles
+                match self
+                    .fetcher
+                    .fetch_subtitles(url, &req.job.cache_dir, lang)
+                    .await
+                {
+                    Ok(Some(path)) => (req.job.clone(), path),
+                    Ok(None) => return fallback(format!("yt-dlp wrote no {lang} subtitles")),
+                    Err(e) => return fallback(format!("subtitle lookup failed: {e}")),
+                }
+            }
+        };
+
+        match load_subtitles(&path) {
+            Ok(transcript) => {
+                bus.publish(Arc::new(AudioTranscribed::new(
+                    event.event.event_id(),
+                    job,
+                    transcript,
+                )));
+                Ok(())
+            }
+            Err(e) => fallback(format!("unusable subtitles: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bratishka_core::events::{Event, downcast_ref};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        provider::Provider,
+        testing::{CaptureBus, FakeMediaFetcher, transcribed_job},
+    };
+
+    const SUBTITLES_VTT: &str =
+        "WEBVTT\n\n00:00:01.000 --> 00:00:04.000\nsubtitles say ownership moves values\n";
+
+    async fn fetch(source: TranscriptSource) -> (anyhow::Result<()>, Vec<Arc<dyn Event>>) {
+        let root = tempfile::tempdir().unwrap();
+        let mut job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        job.transcript_source = source;
+        job.metadata = Some(crate::media::VideoMetadata {
+            subtitle_lang: Some("en".to_string()),
+            ..Default::default()
+        });
+        let fetcher = FakeMediaFetcher::with_subtitles(SUBTITLES_VTT);
+        let mut worker = FetchSubtitlesWorker::new(Arc::new(fetcher));
+        let mut bus = CaptureBus::new(&[
+            AudioTranscribed::EVENT_TYPE,
+            SubtitlesUnavailable::EVENT_TYPE,
+        ]);
+
+        let result = bus
+            .deliver(
+                &mut worker,
+                Arc::new(YoutubeMetadataFetched::new(Uuid::new_v4(), job)),
+            )
+            .await;
+        (result, bus.take())
+    }
+
+    #[tokio::test]
+    async fn subtitles_become_the_transcript() {
+        let (result, published) = fetch(TranscriptSource::Auto).await;
+        result.unwrap();
+
+        let transcribed = downcast_ref::<AudioTranscribed>(&published[0]).unwrap();
+        assert!(
+            transcribed
+                .transcript
+                .text
+                .contains("subtitles say ownership moves values")
+        );
+    }
+
+    #[tokio::test]
+    async fn whisper_source_ignores_subtitles() {
+        let (result, published) = fetch(TranscriptSource::Whisper).await;
+        result.unwrap();
+
+        assert_eq!(published.len(), 1);
+        assert!(downcast_ref::<SubtitlesUnavailable>(&published[0]).is_some());
+    }
+
+    #[tokio::test]
+    async fn subs_source_fails_without_subtitles() {
+        let root = tempfile::tempdir().unwrap();
+        let mut job = transcribed_job(Provider::Grok, root.path(), "http://127.0.0.1:9");
+        job.transcript_source = TranscriptSource::Subs;
+        let mut worker = FetchSubtitlesWorker::new(Arc::new(FakeMediaFetcher::default()));
+        let mut bus = CaptureBus::new(&[SubtitlesUnavailable::EVENT_TYPE]);
+
+        let err = bus
+            .deliver(
+                &mut worker,
+                Arc::new(YoutubeMetadataFetched::new(Uuid::new_v4(), job)),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("rules out whisper"), "{err}");
+        assert!(bus.take().is_empty());
+    }
+}
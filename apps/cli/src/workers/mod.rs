@@ -1,7 +1,16 @@
 pub mod analyze_sections;
+pub mod batch_index;
 pub mod cli_completion_sink;
 pub mod compile_report;
+pub mod diarize_speakers;
 pub mod download_video;
 pub mod events;
+pub mod expand_playlist;
 pub mod extract_audio;
+pub mod fetch_metadata;
+pub mod fetch_subtitles;
 pub mod transcribe_audio;
+
+/// Queue depth for stage inputs; jobs are let in a few at a time (see
+/// `StageConcurrency::jobs_in_flight`), so drop-oldest queues never discard one
+pub const STAGE_QUEUE_CAPACITY: usize = 256;
@@ -1,91 +1,206 @@
-use std::{
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use std::{path::Path, sync::Arc};
 
 use bratishka_core::{
     events::{EnrichedEvent, EventBus, expect},
     queues::QueueKind,
     workers::{InputSpec, SubscriptionSpec, Worker},
 };
-use tokio::fs;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use tokio::{fs, sync::Mutex};
 
 use crate::{
+    cache::{get_transcript_chunks_dir, get_transcript_path},
+    models::{ensure_model, model_name_from_path},
+    timestamp::format_timestamp,
     types::{Segment, Transcript},
-    workers::events::{AudioTranscribed, YoutubeAudioExtracted},
+    whisper::{
+        ChunkRecord, ChunkStore, ChunkedAudio, Device, LoadedModels, SAMPLE_RATE, ScoredSegment,
+        SpeechAudio, TimedToken, WhisperOptions, WhisperPool, filter_segments, find_non_speech,
+        group_words, merge_spans, resolve_device,
+    },
+    workers::{
+        STAGE_QUEUE_CAPACITY,
+        events::{AudioTranscribed, YoutubeAudioExtracted},
+    },
 };
 
-#[derive(Default)]
-pub struct TranscribeAudioWorker;
+#[derive(Clone)]
+pub struct TranscribeAudioWorker {
+    pool: WhisperPool,
+    /// Held while a model is installed, so jobs don't download it twice
+    installing: Arc<Mutex<()>>,
+}
 
 impl TranscribeAudioWorker {
-    pub fn new() -> Self {
-        Self
+    pub fn new(pool: WhisperPool) -> Self {
+        Self {
+            pool,
+            installing: Arc::new(Mutex::new(())),
+        }
     }
 
-    async fn transcribe_audio(
+    /// Runs on a [`WhisperPool`] thread; blocks for as long as decoding takes
+    fn transcribe_audio(
+        models: &LoadedModels,
         audio_path: &Path,
         output_path: &Path,
-        model_path: &PathBuf,
+        chunks: &ChunkStore,
+        model_path: &Path,
+        options: &WhisperOptions,
     ) -> anyhow::Result<Transcript> {
-        let mut reader = hound::WavReader::open(audio_path).unwrap();
-        let samples: Vec<f32> = reader
-            .samples::<i16>()
-            .map(|s| s.unwrap() as f32 / i16::MAX as f32)
-            .collect();
-
-        // load a context and model
-        let mut ctx_params = WhisperContextParameters {
-            use_gpu: true,
-            flash_attn: true,
-            ..Default::default()
+        let model = model_name_from_path(model_path);
+        let mut records = chunks.load(model)?;
+        let resume_from = records.last().map_or(0, |r| r.end);
+        let mut audio = ChunkedAudio::open(audio_path, resume_from)?;
+        let duration = audio.total_samples() as f64 / SAMPLE_RATE as f64;
+        if resume_from > 0 {
+            println!(
+                "resuming transcription of {} at {}",
+                audio_path.display(),
+                format_timestamp(resume_from as f64 / SAMPLE_RATE as f64)
+            );
+        }
+
+        let mut device = resolve_device(options.device)?;
+        let ctx = match models.get(model_path, device) {
+            // a driver that lists the GPU can still fail to set it up
+            Err(err) if device == Device::Gpu && options.device.is_none() => {
+                println!("could not load the model on the GPU ({err}); transcribing on the CPU");
+                device = Device::Cpu;
+                models.get(model_path, device)?
+            }
+            ctx => ctx?,
         };
-        ctx_params.flash_attn = true;
-        let model_path_str = model_path.to_str().unwrap();
-        let ctx = WhisperContext::new_with_params(model_path_str, ctx_params)
-            .expect("failed to load model");
+        let mut state = ctx.create_state()?;
+        // ids from here on are timestamps and other control tokens
+        let first_special_token = ctx.token_eot();
 
-        // create a params object
-        let params = FullParams::new(SamplingStrategy::Greedy { best_of: 5 });
+        while let Some(chunk) = audio.next_chunk()? {
+            let non_speech = if options.vad {
+                find_non_speech(&chunk.samples)
+            } else {
+                Vec::new()
+            };
+            let speech = SpeechAudio::new(&chunk.samples, &non_speech);
+
+            let mut segments: Vec<ScoredSegment> = Vec::new();
+            let mut language = options.output_language().map(str::to_string);
+            // nothing to decode; whisper would only hallucinate over it
+            if !speech.samples.is_empty() {
+                state.full(options.full_params(), &speech.samples)?;
 
-        // now we can run the model
-        let mut state = ctx.create_state().expect("failed to create state");
-        state.full(params, &samples).expect("failed to run model");
+                for segment in state.as_iter() {
+                    let seg_text = match segment.to_str() {
+                        Ok(s) => s,
+                        Err(_) => continue,
+                    };
+                    let tokens: Vec<_> = (0..segment.n_tokens())
+                        .filter_map(|i| segment.get_token(i))
+                        .filter(|token| token.token_id() < first_special_token)
+                        .collect();
+                    let words = group_words(tokens.iter().filter_map(|token| {
+                        let data = token.token_data();
+                        Some(TimedToken {
+                            bytes: token.to_bytes().ok()?,
+                            start: data.t0 as f64 / 100.0,
+                            end: data.t1 as f64 / 100.0,
+                            probability: data.p,
+                        })
+                    }));
+                    let avg_logprob = tokens
+                        .iter()
+                        .map(|token| token.token_data().p.max(f32::MIN_POSITIVE).ln())
+                        .sum::<f32>()
+                        / tokens.len().max(1) as f32;
+                    segments.push(ScoredSegment {
+                        segment: Segment {
+                            start: segment.start_timestamp() as f64 / 100.0,
+                            end: segment.end_timestamp() as f64 / 100.0,
+                            text: seg_text.to_string(),
+                            words,
+                            speaker: None,
+                        },
+                        avg_logprob,
+                        no_speech_probability: segment.no_speech_probability(),
+                    });
+                }
 
-        let mut text = String::new();
-        let mut segments: Vec<Segment> = Vec::new();
+                if language.is_none() {
+                    language = whisper_rs::get_lang_str(state.full_lang_id_from_state())
+                        .map(str::to_string);
+                }
+            }
 
-        for segment in state.as_iter() {
-            let seg_text = match segment.to_str() {
-                Ok(s) => s,
-                Err(_) => continue,
+            let (segments, filtered) = if options.filter {
+                filter_segments(segments)
+            } else {
+                (
+                    segments.into_iter().map(|s| s.segment).collect(),
+                    Vec::new(),
+                )
             };
-            let seg = Segment {
-                start: segment.start_timestamp() as f64 / 100.0,
-                end: segment.end_timestamp() as f64 / 100.0,
-                text: seg_text.to_string(),
+            let filtered = filtered
+                .into_iter()
+                .map(|mut f| {
+                    f.segment = chunk.to_file_time(speech.restore_segment(f.segment));
+                    f
+                })
+                .filter(|f| chunk.owns(&f.segment))
+                .collect();
+
+            let record = ChunkRecord {
+                end: chunk.end,
+                language,
+                model: model.map(str::to_string),
+                segments: chunk.place_segments(speech.restore_times(segments)),
+                non_speech: chunk.place_spans(&non_speech),
+                filtered,
             };
-            segments.push(seg);
+            chunks.save(records.len(), &record)?;
+            records.push(record);
 
-            text.push_str(seg_text);
+            println!(
+                "transcribed {} up to {} of {} (chunk {})",
+                audio_path.display(),
+                format_timestamp(chunk.end as f64 / SAMPLE_RATE as f64),
+                format_timestamp(duration),
+                records.len(),
+            );
         }
 
-        let language_index = state.full_lang_id_from_state();
-        let language = whisper_rs::get_lang_str(language_index);
+        // chunks of a long file can disagree, e.g. over an intro in another language
+        let language = records
+            .iter()
+            .filter_map(|r| r.language.as_deref())
+            .max_by_key(|&lang| {
+                records
+                    .iter()
+                    .filter(|r| r.language.as_deref() == Some(lang))
+                    .count()
+            })
+            .unwrap_or("Unknown")
+            .to_string();
+        let non_speech = merge_spans(records.iter().flat_map(|r| r.non_speech.iter().copied()));
+        let filtered = records.iter().flat_map(|r| r.filtered.clone()).collect();
+        let segments: Vec<Segment> = records.into_iter().flat_map(|r| r.segments).collect();
+        let text = segments.iter().map(|s| s.text.as_str()).collect();
 
         let transcript = Transcript {
-            language: language.unwrap_or("Unknown").to_string(),
+            language,
             segments,
             text,
+            model: model.map(str::to_string),
+            device: Some(device),
+            non_speech,
+            filtered,
         };
 
-        fs::write(output_path, serde_json::to_string_pretty(&transcript)?).await?;
+        std::fs::write(output_path, serde_json::to_string_pretty(&transcript)?)?;
+        chunks.clear()?;
 
         Ok(transcript)
     }
 
-    async fn load_transcript(transcript_path: &std::path::PathBuf) -> anyhow::Result<Transcript> {
+    async fn load_transcript(transcript_path: &Path) -> anyhow::Result<Transcript> {
         let json_content = fs::read_to_string(transcript_path).await?;
         let transcript: Transcript = serde_json::from_str(&json_content)?;
         Ok(transcript)
@@ -100,7 +215,9 @@ impl Worker for TranscribeAudioWorker {
             subscriber_id: Self::SUBSCRIBER_ID,
             inputs: vec![InputSpec {
                 event_type: YoutubeAudioExtracted::EVENT_TYPE,
-                queue_kind: QueueKind::FifoDropOldest { capacity: 4 },
+                queue_kind: QueueKind::FifoDropOldest {
+                    capacity: STAGE_QUEUE_CAPACITY,
+                },
             }],
         }
     }
@@ -108,20 +225,51 @@ impl Worker for TranscribeAudioWorker {
     async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> anyhow::Result<()> {
         let req = expect::<YoutubeAudioExtracted>(&event.event, YoutubeAudioExtracted::EVENT_TYPE)?;
         let audio_path = &req.audio_file_path;
-        let transcript_path = req.job.cache_dir.join("transcript.json");
+        let transcript_path = get_transcript_path(&req.job.cache_dir, &req.job.whisper);
 
         if !req.job.force && transcript_path.exists() {
             let transcript = Self::load_transcript(&transcript_path).await?;
-            bus.publish(Arc::new(AudioTranscribed::new(
-                event.event.event_id(),
-                req.job.clone(),
-                transcript,
-            )));
-            return Ok(());
+            // transcripts from before models were recorded are kept as they are
+            if transcript
+                .model
+                .as_ref()
+                .is_none_or(|model| *model == req.job.model)
+            {
+                bus.publish(Arc::new(AudioTranscribed::new(
+                    event.event.event_id(),
+                    req.job.with_cache_hit(Self::SUBSCRIBER_ID),
+                    transcript,
+                )));
+                return Ok(());
+            }
         }
 
-        let transcript =
-            Self::transcribe_audio(&audio_path, &transcript_path, &req.job.model_path).await?;
+        let chunks = ChunkStore::new(get_transcript_chunks_dir(
+            &req.job.cache_dir,
+            &req.job.whisper,
+        ));
+        if req.job.force {
+            chunks.clear()?;
+        }
+        let audio_path = audio_path.clone();
+        let model_path = {
+            let _installing = self.installing.lock().await;
+            ensure_model(&req.job.root_cache_dir, &req.job.model).await?
+        };
+        let options = req.job.whisper.clone();
+        let transcript = self
+            .pool
+            .run(move |models| {
+                Self::transcribe_audio(
+                    models,
+                    &audio_path,
+                    &transcript_path,
+                    &chunks,
+                    &model_path,
+                    &options,
+                )
+            })
+            .await??;
 
         bus.publish(Arc::new(AudioTranscribed::new(
             event.event.event_id(),
@@ -24,6 +24,12 @@ pub trait Event: Send + Sync + ErasedSerialize + 'static {
     fn event_type(&self) -> &'static str;
     fn timestamp(&self) -> SystemTime;
 
+    /// Shared by every event that belongs to the same unit of work (e.g. one job),
+    /// so consumers can match outcomes to requests across stages
+    fn correlation_id(&self) -> Option<Uuid> {
+        None
+    }
+
     fn schema_version(&self) -> u32 {
         1
     }
@@ -5,13 +5,15 @@ use uuid::Uuid;
 
 use crate::events::Event;
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct PipelineFailed {
     pub event_id: Uuid,
     pub ts: SystemTime,
     pub parents: [Uuid; 1],
     pub stage: &'static str,
     pub message: String,
+    /// Inherited from the event whose handling failed
+    pub correlation_id: Option<Uuid>,
 }
 
 impl PipelineFailed {
@@ -24,6 +26,7 @@ impl PipelineFailed {
             ts: SystemTime::now(),
             parents: [event.event_id()],
             stage: subscriber_id,
+            correlation_id: event.correlation_id(),
         }
     }
 }
@@ -38,13 +41,17 @@ impl Event for PipelineFailed {
     }
 
     fn event_type(&self) -> &'static str {
-        PipelineFailed::TYPE
+        PipelineFailed::EVENT_TYPE
     }
 
     fn timestamp(&self) -> std::time::SystemTime {
         self.ts
     }
 
+    fn correlation_id(&self) -> Option<Uuid> {
+        self.correlation_id
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self as &dyn Any
     }
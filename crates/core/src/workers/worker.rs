@@ -1,7 +1,7 @@
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
 use anyhow::{Ok, Result};
-use tokio::sync::broadcast;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, broadcast};
 
 use crate::{
     events::{EnrichedEvent, EventBus},
@@ -11,7 +11,11 @@ use crate::{
 pub trait Worker: Send + Sized + 'static {
     const SUBSCRIBER_ID: &'static str;
     fn subscription() -> SubscriptionSpec;
-    async fn handle(&mut self, event: Arc<EnrichedEvent>, bus: &EventBus) -> Result<()>;
+    fn handle(
+        &mut self,
+        event: Arc<EnrichedEvent>,
+        bus: &EventBus,
+    ) -> impl Future<Output = Result<()>> + Send;
     async fn run(
         mut self,
         mut inputs: WorkerInputs,
@@ -34,4 +38,74 @@ pub trait Worker: Send + Sized + 'static {
             }
         }
     }
+
+    /// Like [`Worker::run`], but handles up to `concurrency` events at once,
+    /// each on its own clone of the worker
+    fn run_concurrent(
+        self,
+        mut inputs: WorkerInputs,
+        bus: Arc<EventBus>,
+        mut shutdown: broadcast::Receiver<()>,
+        concurrency: usize,
+    ) -> impl Future<Output = Result<()>> + Send
+    where
+        Self: Clone,
+    {
+        async move {
+            let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+            loop {
+                // take a permit first so waiting events stay in the queue,
+                // where its drop policy still applies to them
+                let permit = tokio::select! {
+                    _ = shutdown.recv() => return Ok(()),
+                    permit = Arc::clone(&permits).acquire_owned() => permit?,
+                };
+
+                tokio::select! {
+                    _ = shutdown.recv() => return Ok(()),
+                    batch = inputs.next() => match batch {
+                        WorkerBatch::Snapshots(updates) => {
+                            // the first update uses the permit taken above, the rest wait for theirs
+                            let mut permit = Some(permit);
+                            for update in updates {
+                                let permit = match permit.take() {
+                                    Some(permit) => permit,
+                                    None => tokio::select! {
+                                        _ = shutdown.recv() => return Ok(()),
+                                        permit = Arc::clone(&permits).acquire_owned() => permit?,
+                                    },
+                                };
+                                spawn_handle(&self, &bus, update.event, permit);
+                            }
+                        },
+                        WorkerBatch::FifoItem { event_type: _event_type, event } => {
+                            spawn_handle(&self, &bus, event, permit);
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Handle `event` on a clone of `worker` in its own task, releasing `permit` when done
+fn spawn_handle<W: Worker + Clone>(
+    worker: &W,
+    bus: &Arc<EventBus>,
+    event: Arc<EnrichedEvent>,
+    permit: OwnedSemaphorePermit,
+) {
+    let mut worker = worker.clone();
+    let bus = Arc::clone(bus);
+    tokio::spawn(async move {
+        let parent = Arc::clone(&event);
+        if let Err(e) = worker.handle(event, &bus).await {
+            bus.publish(Arc::new(PipelineFailed::new(
+                Arc::clone(&parent.event),
+                W::SUBSCRIBER_ID,
+                format!("{e}"),
+            )));
+        }
+        drop(permit);
+    });
 }